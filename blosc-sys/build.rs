@@ -1,6 +1,15 @@
 // vim: tw=80
 
 fn main() {
-    println!("cargo:rustc-link-search=native=/usr/local/lib");
-    println!("cargo:rustc-link-lib=blosc");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=BLOSC_LIB_DIR");
+    // With the "static" feature, blosc-src's build script links C-Blosc instead
+    if std::env::var_os("CARGO_FEATURE_STATIC").is_none() {
+        // BLOSC_LIB_DIR selects a custom build of C-Blosc, like a patched
+        // checkout's build directory
+        let lib_dir = std::env::var("BLOSC_LIB_DIR");
+        let lib_dir = lib_dir.as_deref().unwrap_or("/usr/local/lib");
+        println!("cargo:rustc-link-search=native={}", lib_dir);
+        println!("cargo:rustc-link-lib=blosc");
+    }
 }
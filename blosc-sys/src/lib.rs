@@ -5,4 +5,14 @@
 //! These are raw, `unsafe` FFI bindings.  Here be dragons!  You probably
 //! shouldn't use this crate directly.  Instead, you should use the
 //! [`blosc`](https://crates.io/crates/blosc) crate.
+//!
+//! By default, these bindings link to the system's C-Blosc library.  With the
+//! `static` feature, they instead link to a static build of the C-Blosc
+//! sources bundled by the [`blosc-src`](https://crates.io/crates/blosc-src)
+//! crate, with every compressor enabled.  The bindings are the same either
+//! way.
 include!("bindgen.rs");
+
+// Nothing here refers to blosc-src, but it must be linked nonetheless.
+#[cfg(feature = "static")]
+extern crate blosc_src;
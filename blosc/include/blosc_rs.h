@@ -0,0 +1,119 @@
+#ifndef BLOSC_RS_H
+#define BLOSC_RS_H
+
+/* Generated by cbindgen.  Do not edit by hand. */
+
+#include <stddef.h>
+
+/**
+ * The operation succeeded.
+ */
+#define BLOSC_RS_OK 0
+
+/**
+ * An unexpected internal error.
+ */
+#define BLOSC_RS_ERROR_UNSPECIFIED -1
+
+/**
+ * A null pointer, or an out-of-range or unknown setting.
+ */
+#define BLOSC_RS_ERROR_INVALID_ARGUMENT -2
+
+/**
+ * The destination buffer is too small.
+ */
+#define BLOSC_RS_ERROR_DEST_TOO_SMALL -3
+
+/**
+ * The source is not a valid Blosc buffer.
+ */
+#define BLOSC_RS_ERROR_INVALID_DATA -4
+
+/**
+ * The source is a Blosc buffer, but it's corrupt.
+ */
+#define BLOSC_RS_ERROR_CORRUPT_DATA -5
+
+/**
+ * The compressor isn't supported by this build of C-Blosc.
+ */
+#define BLOSC_RS_ERROR_COMPRESSOR_NOT_SUPPORTED -6
+
+/**
+ * The source's checksum doesn't match its contents.
+ */
+#define BLOSC_RS_ERROR_CHECKSUM_MISMATCH -7
+
+/**
+ * Return the largest possible compressed size of `src_len` bytes, including
+ * room for a checksum.  Saturates at `SIZE_MAX`.
+ */
+size_t blosc_rs_max_compressed_len(size_t src_len);
+
+/**
+ * Compress `src_len` bytes from `src` into `dest`.
+ *
+ * * `typesize`: the size of each element, for the shuffle filter.  Must not
+ *   be 0.
+ * * `clevel`: the compression level, from 0 through 9.
+ * * `shuffle`: 0 for none, 1 for byte shuffle, 2 for bit shuffle, or 3 to
+ *   choose automatically.
+ * * `compressor`: C-Blosc's name for the compressor, like `"zstd"`, or null
+ *   for the default.
+ * * `checksum`: nonzero to append a CRC-32C of the compressed data.
+ *
+ * On success, the compressed size is stored in `*dest_written`.  `dest_len`
+ * must be at least [`blosc_rs_max_compressed_len`] of `src_len`.  Otherwise
+ * `BLOSC_RS_ERROR_DEST_TOO_SMALL` is returned, even if the output would have
+ * fit.
+ *
+ * # Safety
+ *
+ * `src` must be valid for reads of `src_len` bytes, and `dest` for writes of
+ * `dest_len` bytes.  `compressor` must be null or a nul-terminated string.
+ * `dest_written` must be valid for writes.
+ */
+int blosc_rs_compress(const void *src,
+                      size_t src_len,
+                      size_t typesize,
+                      int clevel,
+                      int shuffle,
+                      const char *compressor,
+                      int checksum,
+                      void *dest,
+                      size_t dest_len,
+                      size_t *dest_written);
+
+/**
+ * Store the decompressed size of the Blosc buffer at `src` in `*len`.
+ *
+ * Only the header is read, so `src_len` may be as small as 16 bytes.
+ *
+ * # Safety
+ *
+ * `src` must be valid for reads of `src_len` bytes, and `len` must be valid
+ * for writes.
+ */
+int blosc_rs_decompressed_len(const void *src, size_t src_len, size_t *len);
+
+/**
+ * Decompress the Blosc buffer at `src` into `dest`.
+ *
+ * If the buffer has a checksum, it's verified first.  On success, the
+ * decompressed size is stored in `*dest_written`.
+ *
+ * # Safety
+ *
+ * `src` must be valid for reads of `src_len` bytes, and `dest` for writes of
+ * `dest_len` bytes.  `dest_written` must be valid for writes.  The contents
+ * of `src` must come from a trusted source, because C-Blosc doesn't fully
+ * validate them.
+ */
+int blosc_rs_decompress(const void *src,
+                        size_t src_len,
+                        void *dest,
+                        size_t dest_len,
+                        size_t *dest_written);
+
+#endif /* BLOSC_RS_H */
@@ -0,0 +1,268 @@
+// vim: tw=80
+//! An append-only container of Blosc buffers.
+//!
+//! An archive is a sequence of compressed buffers followed by an index.  The
+//! index has one entry per buffer, then the number of entries, then a magic
+//! number:
+//!
+//! ```text
+//! +----------+-----+----------+---------+-----+---------+-------+----------+
+//! | buffer 0 | ... | buffer N | entry 0 | ... | entry N | count | BLOSCIDX |
+//! +----------+-----+----------+---------+-----+---------+-------+----------+
+//! ```
+//!
+//! Each entry is three little-endian `u64`s: the buffer's offset from the start
+//! of the archive, its uncompressed length, and its compressed length.  The
+//! count is also a little-endian `u64`.
+
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    mem,
+};
+
+use crate::{decompress_bytes, Buffer};
+
+const MAGIC: &[u8; 8] = b"BLOSCIDX";
+const TRAILER_LEN: u64 = 16;
+const ENTRY_LEN: u64 = 24;
+
+/// The location of one buffer within an archive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IndexEntry {
+    /// Offset of the buffer from the start of the archive, in bytes.
+    pub offset: u64,
+    /// Length of the buffer's uncompressed data, in bytes.
+    pub uncompressed_len: u64,
+    /// Length of the compressed buffer, in bytes.
+    pub compressed_len: u64,
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; mem::size_of::<u64>()];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Read an archive's index, and return it along with the offset where it
+/// begins.
+fn read_index<R: Read + Seek>(r: &mut R) -> io::Result<(Vec<IndexEntry>, u64)> {
+    let len = r.seek(SeekFrom::End(0))?;
+    if len < TRAILER_LEN {
+        return Err(invalid_data("Archive too short"));
+    }
+    r.seek(SeekFrom::Start(len - TRAILER_LEN))?;
+    let count = read_u64(r)?;
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("Not a Blosc archive"));
+    }
+    let index_start = count
+        .checked_mul(ENTRY_LEN)
+        .and_then(|index_len| (len - TRAILER_LEN).checked_sub(index_len))
+        .ok_or_else(|| invalid_data("Archive index too large"))?;
+    r.seek(SeekFrom::Start(index_start))?;
+    let mut index = Vec::new();
+    for _ in 0..count {
+        let entry = IndexEntry {
+            offset: read_u64(r)?,
+            uncompressed_len: read_u64(r)?,
+            compressed_len: read_u64(r)?,
+        };
+        let end = entry.offset.checked_add(entry.compressed_len);
+        if !matches!(end, Some(end) if end <= index_start) {
+            return Err(invalid_data("Archive index entry out of bounds"));
+        }
+        index.push(entry);
+    }
+    Ok((index, index_start))
+}
+
+/// Writes buffers to an archive.
+///
+/// The index is only written by [`finish`](ArchiveWriter::finish).  An archive
+/// whose writer is dropped without finishing will be unreadable.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// # use std::io::Cursor;
+/// let ctx = Context::new();
+/// let mut writer = ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+/// writer.append(&ctx.compress(&[1u32, 2, 3][..])).unwrap();
+/// writer.append(&ctx.compress(&[4u32, 5][..])).unwrap();
+/// let file = writer.finish().unwrap();
+///
+/// let mut reader = ArchiveReader::new(file).unwrap();
+/// assert_eq!(2, reader.len());
+/// let chunk = unsafe { reader.decompress::<u32>(1) }.unwrap();
+/// assert_eq!(vec![4, 5], chunk);
+/// ```
+pub struct ArchiveWriter<W: Write + Seek> {
+    inner: W,
+    index: Vec<IndexEntry>,
+    pos: u64,
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+    /// Append a compressed buffer to the archive.
+    pub fn append<T>(&mut self, buf: &Buffer<T>) -> io::Result<()> {
+        self.inner.write_all(buf.as_ref())?;
+        let entry = IndexEntry {
+            offset: self.pos,
+            uncompressed_len: buf.sizes().0 as u64,
+            compressed_len: buf.size() as u64,
+        };
+        self.pos += entry.compressed_len;
+        self.index.push(entry);
+        Ok(())
+    }
+
+    /// Write the index and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        for entry in self.index.iter() {
+            self.inner.write_all(&entry.offset.to_le_bytes())?;
+            self.inner
+                .write_all(&entry.uncompressed_len.to_le_bytes())?;
+            self.inner.write_all(&entry.compressed_len.to_le_bytes())?;
+        }
+        self.inner
+            .write_all(&(self.index.len() as u64).to_le_bytes())?;
+        self.inner.write_all(MAGIC)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Start a new, empty archive.
+    ///
+    /// `inner` must be empty, or an [`io::ErrorKind::InvalidInput`] error is
+    /// returned.  Overwriting a longer archive in place would leave its stale
+    /// tail behind, so to reuse a file, truncate it first with
+    /// [`File::set_len`](std::fs::File::set_len).
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        if inner.seek(SeekFrom::End(0))? != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Archive writer is not empty",
+            ));
+        }
+        Ok(ArchiveWriter {
+            inner,
+            index: Vec::new(),
+            pos: 0,
+        })
+    }
+}
+
+impl<W: Read + Write + Seek> ArchiveWriter<W> {
+    /// Reopen a finished archive so more buffers can be appended to it.
+    ///
+    /// New buffers overwrite the old index, and a new index will be written
+    /// by [`finish`](ArchiveWriter::finish).
+    pub fn open(mut inner: W) -> io::Result<Self> {
+        let (index, pos) = read_index(&mut inner)?;
+        inner.seek(SeekFrom::Start(pos))?;
+        Ok(ArchiveWriter { inner, index, pos })
+    }
+}
+
+/// Reads buffers from an archive, in any order.
+pub struct ArchiveReader<R: Read + Seek> {
+    inner: R,
+    index: Vec<IndexEntry>,
+}
+
+impl<R: Read + Seek> ArchiveReader<R> {
+    /// Decompress the `i`th buffer in the archive.
+    ///
+    /// # Safety
+    ///
+    /// The archive's contents are unchecked, so the same requirements apply
+    /// as for [`decompress_bytes`].
+    pub unsafe fn decompress<T: Copy>(&mut self, i: usize) -> io::Result<Vec<T>> {
+        let raw = self.read_raw(i)?;
+        decompress_bytes(&raw[..]).map_err(invalid_data)
+    }
+
+    /// Decompress every buffer in the archive, in order, into one `Vec`.
+    ///
+    /// After each buffer, `progress` is called with the number of buffers
+    /// decompressed so far and the total.  A single buffer can't report its
+    /// own progress, so this suits archives of many small buffers.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for
+    /// [`decompress`](ArchiveReader::decompress).
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// # use std::io::Cursor;
+    /// let ctx = Context::new();
+    /// let mut writer = ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+    /// writer.append(&ctx.compress(&[1u32, 2, 3][..])).unwrap();
+    /// writer.append(&ctx.compress(&[4u32, 5][..])).unwrap();
+    /// let mut reader = ArchiveReader::new(writer.finish().unwrap()).unwrap();
+    ///
+    /// let mut reports = Vec::new();
+    /// let all = unsafe {
+    ///     reader.decompress_with_progress::<u32, _>(|done, total| reports.push((done, total)))
+    /// };
+    /// assert_eq!(vec![1, 2, 3, 4, 5], all.unwrap());
+    /// assert_eq!(vec![(1, 2), (2, 2)], reports);
+    /// ```
+    pub unsafe fn decompress_with_progress<T, F>(&mut self, mut progress: F) -> io::Result<Vec<T>>
+    where
+        T: Copy,
+        F: FnMut(usize, usize),
+    {
+        let total = self.index.len();
+        let mut all = Vec::new();
+        for i in 0..total {
+            all.extend_from_slice(&self.decompress::<T>(i)?[..]);
+            progress(i + 1, total);
+        }
+        Ok(all)
+    }
+
+    /// Return the archive's index.
+    pub fn index(&self) -> &[IndexEntry] {
+        &self.index[..]
+    }
+
+    /// Return `true` if the archive contains no buffers.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Return the number of buffers in the archive.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Open an archive for reading.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let (index, _) = read_index(&mut inner)?;
+        Ok(ArchiveReader { inner, index })
+    }
+
+    /// Read the raw compressed bytes of the `i`th buffer in the archive.
+    pub fn read_raw(&mut self, i: usize) -> io::Result<Vec<u8>> {
+        let entry = self
+            .index
+            .get(i)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No such buffer"))?;
+        let mut raw = vec![0u8; entry.compressed_len as usize];
+        self.inner.seek(SeekFrom::Start(entry.offset))?;
+        self.inner.read_exact(&mut raw[..])?;
+        Ok(raw)
+    }
+}
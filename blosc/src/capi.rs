@@ -0,0 +1,243 @@
+// vim: tw=80
+//! A C API for this crate, enabled by the `capi` feature.
+//!
+//! These functions expose the crate's own behavior, like its checksum
+//! framing, to C programs.  The matching header is `include/blosc_rs.h`,
+//! generated by `cbindgen`:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --crate blosc --output include/blosc_rs.h
+//! ```
+//!
+//! Every function returns `BLOSC_RS_OK` on success, or one of the negative
+//! `BLOSC_RS_ERROR_*` codes on failure.
+
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_void},
+    panic, slice,
+};
+
+use crate::{
+    cbuffer_sizes, checked_frame, decompress_to_ptr, BloscError, Checksum, Clevel, Compressor,
+    Context, ShuffleMode,
+};
+
+/// The operation succeeded.
+pub const BLOSC_RS_OK: c_int = 0;
+/// An unexpected internal error.
+pub const BLOSC_RS_ERROR_UNSPECIFIED: c_int = -1;
+/// A null pointer, or an out-of-range or unknown setting.
+pub const BLOSC_RS_ERROR_INVALID_ARGUMENT: c_int = -2;
+/// The destination buffer is too small.
+pub const BLOSC_RS_ERROR_DEST_TOO_SMALL: c_int = -3;
+/// The source is not a valid Blosc buffer.
+pub const BLOSC_RS_ERROR_INVALID_DATA: c_int = -4;
+/// The source is a Blosc buffer, but it's corrupt.
+pub const BLOSC_RS_ERROR_CORRUPT_DATA: c_int = -5;
+/// The compressor isn't supported by this build of C-Blosc.
+pub const BLOSC_RS_ERROR_COMPRESSOR_NOT_SUPPORTED: c_int = -6;
+/// The source's checksum doesn't match its contents.
+pub const BLOSC_RS_ERROR_CHECKSUM_MISMATCH: c_int = -7;
+
+fn error_code(e: BloscError) -> c_int {
+    match e {
+        BloscError::CompressorNotSupported | BloscError::CompressorUnavailable(_) => {
+            BLOSC_RS_ERROR_COMPRESSOR_NOT_SUPPORTED
+        }
+        BloscError::UnknownCompressor => BLOSC_RS_ERROR_INVALID_ARGUMENT,
+        BloscError::CorruptData => BLOSC_RS_ERROR_CORRUPT_DATA,
+        BloscError::ChecksumMismatch => BLOSC_RS_ERROR_CHECKSUM_MISMATCH,
+        BloscError::DestTooSmall => BLOSC_RS_ERROR_DEST_TOO_SMALL,
+        BloscError::ValidationError
+        | BloscError::Truncated
+        | BloscError::TypesizeMismatch
+        | BloscError::LengthMismatch => BLOSC_RS_ERROR_INVALID_DATA,
+        _ => BLOSC_RS_ERROR_UNSPECIFIED,
+    }
+}
+
+/// Run `f`, converting its result into a status code.  Panics must not unwind
+/// into C.
+fn status<F>(f: F) -> c_int
+where
+    F: FnOnce() -> Result<(), c_int> + panic::UnwindSafe,
+{
+    match panic::catch_unwind(f) {
+        Ok(Ok(())) => BLOSC_RS_OK,
+        Ok(Err(code)) => code,
+        Err(_) => BLOSC_RS_ERROR_UNSPECIFIED,
+    }
+}
+
+/// Build a slice from a C pointer and length, allowing null for empty slices.
+unsafe fn src_slice<'a>(src: *const c_void, len: usize) -> Result<&'a [u8], c_int> {
+    if src.is_null() {
+        if len == 0 {
+            Ok(&[])
+        } else {
+            Err(BLOSC_RS_ERROR_INVALID_ARGUMENT)
+        }
+    } else {
+        Ok(slice::from_raw_parts(src as *const u8, len))
+    }
+}
+
+fn clevel(clevel: c_int) -> Result<Clevel, c_int> {
+    match clevel {
+        0 => Ok(Clevel::None),
+        1 => Ok(Clevel::L1),
+        2 => Ok(Clevel::L2),
+        3 => Ok(Clevel::L3),
+        4 => Ok(Clevel::L4),
+        5 => Ok(Clevel::L5),
+        6 => Ok(Clevel::L6),
+        7 => Ok(Clevel::L7),
+        8 => Ok(Clevel::L8),
+        9 => Ok(Clevel::L9),
+        _ => Err(BLOSC_RS_ERROR_INVALID_ARGUMENT),
+    }
+}
+
+fn shuffle(shuffle: c_int) -> Result<ShuffleMode, c_int> {
+    match shuffle {
+        0 => Ok(ShuffleMode::None),
+        1 => Ok(ShuffleMode::Byte),
+        2 => Ok(ShuffleMode::Bit),
+        3 => Ok(ShuffleMode::Auto),
+        _ => Err(BLOSC_RS_ERROR_INVALID_ARGUMENT),
+    }
+}
+
+/// Return the largest possible compressed size of `src_len` bytes, including
+/// room for a checksum.  Saturates at `SIZE_MAX`.
+#[no_mangle]
+pub extern "C" fn blosc_rs_max_compressed_len(src_len: usize) -> usize {
+    Context::new()
+        .with_checksum(Checksum::Crc32c)
+        .max_compressed_len(src_len)
+}
+
+/// Compress `src_len` bytes from `src` into `dest`.
+///
+/// * `typesize`: the size of each element, for the shuffle filter.  Must not
+///   be 0.
+/// * `clevel`: the compression level, from 0 through 9.
+/// * `shuffle`: 0 for none, 1 for byte shuffle, 2 for bit shuffle, or 3 to
+///   choose automatically.
+/// * `compressor`: C-Blosc's name for the compressor, like `"zstd"`, or null
+///   for the default.
+/// * `checksum`: nonzero to append a CRC-32C of the compressed data.
+///
+/// On success, the compressed size is stored in `*dest_written`.  `dest_len`
+/// must be at least [`blosc_rs_max_compressed_len`] of `src_len`.  Otherwise
+/// `BLOSC_RS_ERROR_DEST_TOO_SMALL` is returned, even if the output would have
+/// fit.
+///
+/// # Safety
+///
+/// `src` must be valid for reads of `src_len` bytes, and `dest` for writes of
+/// `dest_len` bytes.  `compressor` must be null or a nul-terminated string.
+/// `dest_written` must be valid for writes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn blosc_rs_compress(
+    src: *const c_void,
+    src_len: usize,
+    typesize: usize,
+    clevel: c_int,
+    shuffle: c_int,
+    compressor: *const c_char,
+    checksum: c_int,
+    dest: *mut c_void,
+    dest_len: usize,
+    dest_written: *mut usize,
+) -> c_int {
+    status(|| {
+        let src = src_slice(src, src_len)?;
+        // C-Blosc divides by the typesize
+        if dest.is_null() || dest_written.is_null() || typesize == 0 {
+            return Err(BLOSC_RS_ERROR_INVALID_ARGUMENT);
+        }
+        let mut ctx = Context::new()
+            .clevel(self::clevel(clevel)?)
+            .shuffle(self::shuffle(shuffle)?)
+            .typesize(Some(typesize));
+        if !compressor.is_null() {
+            let name = CStr::from_ptr(compressor)
+                .to_str()
+                .map_err(|_| BLOSC_RS_ERROR_INVALID_ARGUMENT)?;
+            let compressor: Compressor = name.parse().map_err(error_code)?;
+            ctx = ctx.compressor(compressor).map_err(error_code)?;
+        }
+        if checksum != 0 {
+            ctx = ctx.with_checksum(Checksum::Crc32c);
+        }
+        let dest = slice::from_raw_parts_mut(dest as *mut u8, dest_len);
+        *dest_written = ctx.compress_into_slice(src, dest).map_err(error_code)?;
+        Ok(())
+    })
+}
+
+/// Store the decompressed size of the Blosc buffer at `src` in `*len`.
+///
+/// Only the header is read, so `src_len` may be as small as 16 bytes.
+///
+/// # Safety
+///
+/// `src` must be valid for reads of `src_len` bytes, and `len` must be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn blosc_rs_decompressed_len(
+    src: *const c_void,
+    src_len: usize,
+    len: *mut usize,
+) -> c_int {
+    status(|| {
+        let src = src_slice(src, src_len)?;
+        if len.is_null() {
+            return Err(BLOSC_RS_ERROR_INVALID_ARGUMENT);
+        }
+        let (nbytes, _cbytes, _blocksize) = cbuffer_sizes(src).map_err(error_code)?;
+        *len = nbytes;
+        Ok(())
+    })
+}
+
+/// Decompress the Blosc buffer at `src` into `dest`.
+///
+/// If the buffer has a checksum, it's verified first.  On success, the
+/// decompressed size is stored in `*dest_written`.
+///
+/// # Safety
+///
+/// `src` must be valid for reads of `src_len` bytes, and `dest` for writes of
+/// `dest_len` bytes.  `dest_written` must be valid for writes.  The contents
+/// of `src` must come from a trusted source, because C-Blosc doesn't fully
+/// validate them.
+#[no_mangle]
+pub unsafe extern "C" fn blosc_rs_decompress(
+    src: *const c_void,
+    src_len: usize,
+    dest: *mut c_void,
+    dest_len: usize,
+    dest_written: *mut usize,
+) -> c_int {
+    status(|| {
+        let src = src_slice(src, src_len)?;
+        if dest.is_null() || dest_written.is_null() {
+            return Err(BLOSC_RS_ERROR_INVALID_ARGUMENT);
+        }
+        let (nbytes, cbytes, _blocksize) = cbuffer_sizes(src).map_err(error_code)?;
+        if cbytes > src.len() {
+            return Err(error_code(BloscError::Truncated));
+        }
+        if nbytes > dest_len {
+            return Err(BLOSC_RS_ERROR_DEST_TOO_SMALL);
+        }
+        let frame = checked_frame(src).map_err(error_code)?;
+        decompress_to_ptr(frame, dest as *mut u8, nbytes).map_err(error_code)?;
+        *dest_written = nbytes;
+        Ok(())
+    })
+}
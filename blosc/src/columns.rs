@@ -0,0 +1,304 @@
+// vim: tw=80
+//! Several typed columns, compressed separately and stored in one blob.
+//!
+//! A column set begins with a schema, which has the number of columns, one
+//! entry per column, the columns' names, and their type tags.  The compressed
+//! columns follow, in order:
+//!
+//! ```text
+//! +-------+---------+-----+---------+-------+-------+-------+-----+-------+
+//! | count | entry 0 | ... | entry N | names | types | col 0 | ... | col N |
+//! +-------+---------+-----+---------+-------+-------+-------+-----+-------+
+//! ```
+//!
+//! Each entry is four little-endian `u64`s: the size of the column's element
+//! type, the length of its name, the length of its type tag, and its
+//! compressed length.  The count is also a little-endian `u64`.  The names
+//! and type tags are UTF-8, and are simply concatenated.  Unnamed columns have
+//! empty names.  A type tag is the element type's [`type_name`].
+
+use std::{any::type_name, mem, str};
+
+use crate::{checked_frame, decompress_bytes, BloscError, Context, Plain, Result};
+
+const WORD: usize = mem::size_of::<u64>();
+const ENTRY_WORDS: usize = 4;
+
+/// One column of a [`ColumnSet`], already compressed.
+#[derive(Clone, Debug)]
+struct Entry {
+    elem_size: usize,
+    name: String,
+    type_name: &'static str,
+    data: Vec<u8>,
+}
+
+/// Builds a blob of separately compressed columns.
+///
+/// Each column may have a different element type, and is compressed with its
+/// own `Context`.  Read the blob with [`ColumnReader`].
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let mut columns = ColumnSet::new();
+/// columns.push(&Context::new(), &[1i64, 2, 3][..]);
+/// columns.push_named("ratio", &Context::new().clevel(Clevel::L9), &[0.5f32, 1.5][..]);
+/// let blob = columns.finish();
+///
+/// let reader = ColumnReader::new(&blob[..]).unwrap();
+/// assert_eq!(2, reader.len());
+/// assert_eq!(vec![1i64, 2, 3], unsafe { reader.decompress::<i64>(0) }.unwrap());
+/// assert_eq!(vec![0.5f32, 1.5], reader.column::<f32>("ratio").unwrap());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ColumnSet {
+    columns: Vec<Entry>,
+}
+
+impl ColumnSet {
+    /// Write the schema and columns into a single blob.
+    pub fn finish(self) -> Vec<u8> {
+        let entries_len = (ENTRY_WORDS * self.columns.len() + 1) * WORD;
+        let strings_len: usize = self
+            .columns
+            .iter()
+            .map(|entry| entry.name.len() + entry.type_name.len())
+            .sum();
+        let data_len: usize = self.columns.iter().map(|entry| entry.data.len()).sum();
+        let mut blob = Vec::with_capacity(entries_len + strings_len + data_len);
+        blob.extend_from_slice(&(self.columns.len() as u64).to_le_bytes());
+        for entry in self.columns.iter() {
+            blob.extend_from_slice(&(entry.elem_size as u64).to_le_bytes());
+            blob.extend_from_slice(&(entry.name.len() as u64).to_le_bytes());
+            blob.extend_from_slice(&(entry.type_name.len() as u64).to_le_bytes());
+            blob.extend_from_slice(&(entry.data.len() as u64).to_le_bytes());
+        }
+        for entry in self.columns.iter() {
+            blob.extend_from_slice(entry.name.as_bytes());
+        }
+        for entry in self.columns.iter() {
+            blob.extend_from_slice(entry.type_name.as_bytes());
+        }
+        for entry in self.columns.iter() {
+            blob.extend_from_slice(&entry.data[..]);
+        }
+        blob
+    }
+
+    /// Start a new, empty column set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compress `column` with `ctx`, and append it to the set without a
+    /// name.
+    pub fn push<T>(&mut self, ctx: &Context, column: &[T]) {
+        self.push_named("", ctx, column)
+    }
+
+    /// Compress `column` with `ctx`, and append it to the set with a name.
+    ///
+    /// The column can still be read by its position, too.
+    pub fn push_named<T>(&mut self, name: &str, ctx: &Context, column: &[T]) {
+        let compressed = ctx.compress(column);
+        self.columns.push(Entry {
+            elem_size: mem::size_of::<T>(),
+            name: name.to_owned(),
+            type_name: type_name::<T>(),
+            data: compressed.into(),
+        });
+    }
+}
+
+/// Builds a blob of named columns, which can be read back by name without
+/// `unsafe`.
+///
+/// It's like a [`ColumnSet`], but only accepts [`Plain`] element types, so
+/// that [`ColumnReader::column`] can reconstruct them safely.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let ids: Vec<u32> = (0..100).collect();
+/// let temps: Vec<f64> = (0..100).map(|i| f64::from(i) / 10.0).collect();
+/// let mut builder = ColumnSetBuilder::new();
+/// builder
+///     .add_column("id", &ids[..], &Context::new())
+///     .add_column("temperature", &temps[..], &Context::new().clevel(Clevel::L9));
+/// let blob = builder.build();
+///
+/// let reader = ColumnReader::new(&blob[..]).unwrap();
+/// assert_eq!(temps, reader.column::<f64>("temperature").unwrap());
+/// assert_eq!(Err(BloscError::TypeMismatch), reader.column::<f32>("id"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ColumnSetBuilder {
+    columns: ColumnSet,
+}
+
+impl ColumnSetBuilder {
+    /// Compress `data` with `ctx`, and add it as a column named `name`.
+    pub fn add_column<T: Plain>(&mut self, name: &str, data: &[T], ctx: &Context) -> &mut Self {
+        self.columns.push_named(name, ctx, data);
+        self
+    }
+
+    /// Write the schema and columns into a single blob, like
+    /// [`ColumnSet::finish`].
+    pub fn build(self) -> Vec<u8> {
+        self.columns.finish()
+    }
+
+    /// Start a new, empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// One column of a blob, as found by [`ColumnReader`].
+#[derive(Clone, Copy, Debug)]
+struct Column<'a> {
+    elem_size: usize,
+    name: &'a str,
+    type_name: &'a str,
+    data: &'a [u8],
+}
+
+/// Reads the columns of a blob built by [`ColumnSet`] or [`ColumnSetBuilder`],
+/// in any order.
+#[derive(Clone, Debug)]
+pub struct ColumnReader<'a> {
+    columns: Vec<Column<'a>>,
+}
+
+impl<'a> ColumnReader<'a> {
+    /// Decompress the column named `name`.
+    ///
+    /// If several columns have that name, the first one is used.  Returns
+    /// [`BloscError::ColumnNotFound`] if none do, or
+    /// [`BloscError::TypeMismatch`] if `T` isn't the column's original element
+    /// type, according to its type tag.
+    pub fn column<T: Plain>(&self, name: &str) -> Result<Vec<T>> {
+        let i = self
+            .columns
+            .iter()
+            .position(|column| column.name == name)
+            .ok_or(BloscError::ColumnNotFound)?;
+        if self.columns[i].type_name != type_name::<T>() {
+            return Err(BloscError::TypeMismatch);
+        }
+        // Safe because every bit pattern is valid for a Plain type
+        unsafe { self.decompress(i) }
+    }
+
+    /// Decompress the `i`th column.
+    ///
+    /// Returns [`BloscError::TypeMismatch`] if `T` isn't the same size as the
+    /// column's original element type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// The blob's contents are unchecked, and only the element size is
+    /// checked, not the type tag.  So the same requirements apply as for
+    /// [`decompress_bytes`].
+    pub unsafe fn decompress<T: Copy>(&self, i: usize) -> Result<Vec<T>> {
+        let column = self.columns[i];
+        if column.elem_size != mem::size_of::<T>() {
+            return Err(BloscError::TypeMismatch);
+        }
+        decompress_bytes(checked_frame(column.data)?)
+    }
+
+    /// Return `true` if the blob contains no columns.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Return the number of columns in the blob.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Return the name of the `i`th column, which is empty if it was pushed
+    /// without one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn name(&self, i: usize) -> &'a str {
+        self.columns[i].name
+    }
+
+    /// Return the type tag of the `i`th column: its element type's
+    /// [`type_name`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn type_name(&self, i: usize) -> &'a str {
+        self.columns[i].type_name
+    }
+
+    /// Parse the schema of a blob built by [`ColumnSet`].
+    ///
+    /// Returns [`BloscError::ValidationError`] if the schema is malformed or
+    /// the blob is too short for it.
+    pub fn new(src: &'a [u8]) -> Result<Self> {
+        let read_u64 = |i: usize| -> Result<usize> {
+            let bytes = src.get(i * WORD..(i + 1) * WORD);
+            let bytes = bytes.ok_or(BloscError::ValidationError)?;
+            let mut buf = [0u8; WORD];
+            buf.copy_from_slice(bytes);
+            Ok(u64::from_le_bytes(buf) as usize)
+        };
+        let take = |pos: &mut usize, len: usize| -> Result<&'a [u8]> {
+            let end = pos.checked_add(len).ok_or(BloscError::ValidationError)?;
+            let data = src.get(*pos..end).ok_or(BloscError::ValidationError)?;
+            *pos = end;
+            Ok(data)
+        };
+        let count = read_u64(0)?;
+        let mut pos = count
+            .checked_mul(ENTRY_WORDS)
+            .and_then(|words| words.checked_add(1))
+            .and_then(|words| words.checked_mul(WORD))
+            .ok_or(BloscError::ValidationError)?;
+        let mut entries = Vec::new();
+        for i in 0..count {
+            let elem_size = read_u64(ENTRY_WORDS * i + 1)?;
+            let name_len = read_u64(ENTRY_WORDS * i + 2)?;
+            let type_len = read_u64(ENTRY_WORDS * i + 3)?;
+            let len = read_u64(ENTRY_WORDS * i + 4)?;
+            entries.push((elem_size, name_len, type_len, len));
+        }
+        let take_str = |pos: &mut usize, len: usize| -> Result<&'a str> {
+            str::from_utf8(take(pos, len)?).map_err(|_| BloscError::ValidationError)
+        };
+        let mut names = Vec::with_capacity(count);
+        for (_, name_len, _, _) in entries.iter() {
+            names.push(take_str(&mut pos, *name_len)?);
+        }
+        let mut type_names = Vec::with_capacity(count);
+        for (_, _, type_len, _) in entries.iter() {
+            type_names.push(take_str(&mut pos, *type_len)?);
+        }
+        let mut columns = Vec::with_capacity(count);
+        for ((elem_size, _, _, len), (name, type_name)) in
+            entries.into_iter().zip(names.into_iter().zip(type_names))
+        {
+            let data = take(&mut pos, len)?;
+            columns.push(Column {
+                elem_size,
+                name,
+                type_name,
+                data,
+            });
+        }
+        Ok(ColumnReader { columns })
+    }
+}
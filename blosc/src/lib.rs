@@ -23,21 +23,77 @@
 
 use blosc_sys::*;
 use std::{
-    convert::Into,
+    cell::RefCell,
+    cmp,
+    convert::{Into, TryFrom},
+    ffi::{CStr, CString},
+    fmt,
     hash::{Hash, Hasher},
+    io::{self, Read, Write},
     marker::PhantomData,
     os::raw::{c_char, c_int, c_void},
-    {mem, ptr},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Once, PoisonError, RwLock,
+    },
+    {mem, ptr, slice},
 };
 use thiserror::Error;
 
+mod archive;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod columns;
+mod crc32c;
+#[cfg(feature = "serde")]
+mod tagged;
+
+pub use archive::{ArchiveReader, ArchiveWriter, IndexEntry};
+pub use columns::{ColumnReader, ColumnSet, ColumnSetBuilder};
+#[cfg(feature = "serde")]
+pub use tagged::{decompress_tagged, TaggedBuffer};
+
 /// An unspecified error from C-Blosc
 #[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
 pub enum BloscError {
     #[error("Compressor not supported by this build of c-Blosc")]
     CompressorNotSupported,
+    /// The buffer was compressed with a compressor that this build of
+    /// C-Blosc was built without.
+    ///
+    /// The message names the missing compression library.  Either rebuild the
+    /// system's C-Blosc with it, or enable this crate's `static` feature,
+    /// which bundles a C-Blosc with every compressor.
+    #[error(
+        "Buffer was compressed with {}, but c-Blosc was built without it",
+        .0.name()
+    )]
+    CompressorUnavailable(Compressor),
+    #[error("Unknown compressor name")]
+    UnknownCompressor,
+    #[error("Compressed data is corrupt")]
+    CorruptData,
+    #[error("Checksum does not match the compressed data")]
+    ChecksumMismatch,
     #[error("Not a valid Blosc buffer")]
     ValidationError,
+    #[error("Compressed data is shorter than its header claims")]
+    Truncated,
+    #[error("Decompressed size would exceed the limit")]
+    SizeLimitExceeded,
+    #[error("Destination buffer is too small")]
+    DestTooSmall,
+    #[error("Decompressed size is not a multiple of the element size")]
+    TypesizeMismatch,
+    #[error("Decompressed length does not match the expected length")]
+    LengthMismatch,
+    #[error("Buffer was compressed from a different type")]
+    TypeMismatch,
+    #[error("No column has that name")]
+    ColumnNotFound,
+    #[error("Decompressed data is not valid UTF-8")]
+    InvalidUtf8,
     #[error("unspecified error from c-Blosc")]
     Unspecified,
 }
@@ -64,6 +120,17 @@ pub enum Clevel {
 }
 
 const BLOSC_INVALID_COMPNAME: &[u8; 8usize] = b"invalid\0";
+/// The header flag for unsplit blocks.  C-Blosc doesn't name it.
+const BLOSC_DONT_SPLIT: u8 = 0x10;
+/// The smallest blocksize that C-Blosc will use.  C-Blosc calls it
+/// `MIN_BUFFERSIZE`, but doesn't export it.
+const BLOSC_MIN_BLOCKSIZE: usize = 128;
+/// The largest blocksize that C-Blosc will use.  The C macro
+/// `BLOSC_MAX_BLOCKSIZE` is too complicated for bindgen.
+const BLOSC_MAX_BLOCKSIZE: usize =
+    (i32::MAX as usize - BLOSC_MAX_TYPESIZE as usize * mem::size_of::<i32>()) / 3;
+/// About how much of the input [`Context::lint_data`] inspects, in bytes.
+const LINT_SAMPLE_LEN: usize = 64 * 1024;
 
 /// Compressor selection.
 ///
@@ -75,6 +142,11 @@ pub enum Compressor {
     /// compression isn't as good as the other compressors.
     BloscLZ,
     /// Another fast compressor.  See [lz4.org](http://www.lz4.org).
+    ///
+    /// C-Blosc derives LZ4's acceleration parameter from the compression
+    /// level, as `10 - clevel`.  So `Clevel::L1` is the fastest setting, with
+    /// an acceleration of 9.  C-Blosc 1.x has no way to choose the
+    /// acceleration independently.
     LZ4,
     /// Slower, higher compression version of LZ4.
     /// See [lz4.org](http://www.lz4.org).
@@ -87,12 +159,90 @@ pub enum Compressor {
     Zlib,
     /// A high compression algorithm from Facebook.
     /// See [zstd](https://facebook.github.io/zstd).
+    ///
+    /// C-Blosc 1.x creates its Zstd contexts internally, so there is no way to
+    /// compress or decompress with a trained dictionary.  Streams of small,
+    /// similar records compress better when batched into a single buffer.
     Zstd,
     /// For testing purposes only
     #[doc(hidden)]
     Invalid,
 }
 
+impl Compressor {
+    /// Return C-Blosc's numeric code for this compressor.
+    ///
+    /// This is the same numbering that C-Blosc uses internally, so it is a
+    /// stable and compact way to serialize the choice of compressor.
+    pub const fn to_id(&self) -> u8 {
+        match self {
+            Compressor::BloscLZ => BLOSC_BLOSCLZ as u8,
+            Compressor::LZ4 => BLOSC_LZ4 as u8,
+            Compressor::LZ4HC => BLOSC_LZ4HC as u8,
+            Compressor::Snappy => BLOSC_SNAPPY as u8,
+            Compressor::Zlib => BLOSC_ZLIB as u8,
+            Compressor::Zstd => BLOSC_ZSTD as u8,
+            Compressor::Invalid => u8::MAX,
+        }
+    }
+
+    /// Look up a compressor by C-Blosc's numeric code, the inverse of
+    /// [`to_id`](Compressor::to_id).
+    ///
+    /// Returns `None` if the code does not correspond to any known compressor.
+    /// A compressor may be known but still not be supported by this build of
+    /// C-Blosc.
+    pub const fn from_id(id: u8) -> Option<Compressor> {
+        match id as u32 {
+            BLOSC_BLOSCLZ => Some(Compressor::BloscLZ),
+            BLOSC_LZ4 => Some(Compressor::LZ4),
+            BLOSC_LZ4HC => Some(Compressor::LZ4HC),
+            BLOSC_SNAPPY => Some(Compressor::Snappy),
+            BLOSC_ZLIB => Some(Compressor::Zlib),
+            BLOSC_ZSTD => Some(Compressor::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// The default is `BloscLZ`, regardless of [`set_default_compressor`].
+impl Default for Compressor {
+    fn default() -> Self {
+        Compressor::BloscLZ
+    }
+}
+
+impl Compressor {
+    /// Identify the compressor used for a Blosc buffer with a complete header.
+    ///
+    /// The header only records the compression library, so `LZ4HC` buffers
+    /// are reported as `LZ4`.
+    fn from_header(src: &[u8]) -> Option<Self> {
+        match u32::from((src[2] & 0xe0) >> 5) {
+            BLOSC_BLOSCLZ_FORMAT => Some(Compressor::BloscLZ),
+            BLOSC_LZ4_FORMAT => Some(Compressor::LZ4),
+            BLOSC_SNAPPY_FORMAT => Some(Compressor::Snappy),
+            BLOSC_ZLIB_FORMAT => Some(Compressor::Zlib),
+            BLOSC_ZSTD_FORMAT => Some(Compressor::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Return C-Blosc's name for this compressor.
+    fn name(self) -> &'static str {
+        let name: *const c_char = self.into();
+        // The names are static, nul-terminated ASCII strings
+        unsafe { CStr::from_ptr(name) }.to_str().unwrap()
+    }
+
+    /// Is this compressor enabled in this build of C-Blosc?
+    fn supported(self) -> bool {
+        let comp_ptr: *const c_char = self.into();
+        let support = unsafe { blosc_get_complib_info(comp_ptr, ptr::null_mut(), ptr::null_mut()) };
+        support >= 0
+    }
+}
+
 impl From<Compressor> for *const c_char {
     fn from(compressor: Compressor) -> Self {
         let compref = match compressor {
@@ -108,6 +258,27 @@ impl From<Compressor> for *const c_char {
     }
 }
 
+impl FromStr for Compressor {
+    type Err = BloscError;
+
+    /// Parse a compressor from its C-Blosc name, like `"lz4"`.
+    ///
+    /// Returns [`BloscError::UnknownCompressor`] if the name isn't recognized.
+    /// A recognized compressor may still not be supported by this build of
+    /// C-Blosc.
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "blosclz" => Ok(Compressor::BloscLZ),
+            "lz4" => Ok(Compressor::LZ4),
+            "lz4hc" => Ok(Compressor::LZ4HC),
+            "snappy" => Ok(Compressor::Snappy),
+            "zlib" => Ok(Compressor::Zlib),
+            "zstd" => Ok(Compressor::Zstd),
+            _ => Err(BloscError::UnknownCompressor),
+        }
+    }
+}
+
 /// Controls Blosc's shuffle operation.
 ///
 /// The Shuffle operation is the key to efficiently compressing arrays.  It
@@ -133,289 +304,3764 @@ pub enum ShuffleMode {
     ///
     /// See [new-bitshuffle-filter](http://blosc.org/posts/new-bitshuffle-filter/)
     Bit = BLOSC_BITSHUFFLE as i32,
+
+    /// Let the crate choose between `Byte` and `Bit` based on the typesize.
+    ///
+    /// C-Blosc has no automatic shuffle mode of its own, so the choice is made
+    /// at compression time.  Byte shuffle is a no-op for 1-byte elements, so
+    /// they get `Bit` shuffle.  Everything else gets `Byte` shuffle.
+    Auto = 3,
+}
+
+impl ShuffleMode {
+    /// Read the shuffle mode from a Blosc header's flags.
+    fn from_flags(flags: BufferFlags) -> Self {
+        if flags.bit_shuffle() {
+            ShuffleMode::Bit
+        } else if flags.byte_shuffle() {
+            ShuffleMode::Byte
+        } else {
+            ShuffleMode::None
+        }
+    }
+
+    /// Resolve `Auto` into the concrete mode to use for the given typesize.
+    const fn resolve(self, typesize: usize) -> Self {
+        match self {
+            ShuffleMode::Auto if typesize == 1 => ShuffleMode::Bit,
+            ShuffleMode::Auto => ShuffleMode::Byte,
+            mode => mode,
+        }
+    }
+}
+
+/// Controls whether C-Blosc splits blocks before compressing them.
+///
+/// When a block is split, each byte of the element type is compressed
+/// separately.  That's usually faster and sometimes compresses better,
+/// especially when combined with a shuffle filter.  Blocks are never split when
+/// the typesize is larger than 16 or when a block holds fewer than 128
+/// elements, except with `Always`.
+///
+/// # Typesize, blocksize, and splitting
+///
+/// Those thresholds are fixed in C-Blosc, so `Always` and `Never` are the only
+/// way to override them.  C-Blosc also rounds every blocksize down to a
+/// multiple of the typesize, so blocks always hold whole elements.  To make
+/// blocks line up with records, like 12-byte structs, set the typesize to the
+/// record size and, if `getitem`-style access matters, choose an explicit
+/// [`blocksize`](Context::blocksize) that is a multiple of it.
+///
+/// The blocksize is also affected: when blocks will be split, it's multiplied
+/// by the typesize, within the range of 64 KiB to 1 MiB.  That applies even to
+/// an explicit [`blocksize`](Context::blocksize), unless the compression level
+/// is `Clevel::None`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(i32)]
+pub enum SplitMode {
+    /// Always split blocks, with every compressor.
+    Always = BLOSC_ALWAYS_SPLIT as i32,
+    /// Never split blocks.
+    Never = BLOSC_NEVER_SPLIT as i32,
+    /// Split blocks only for the compressors that usually benefit from it:
+    /// `BloscLZ` and `Snappy`.
+    Auto = BLOSC_AUTO_SPLIT as i32,
+    /// Split blocks for every compressor except `Zstd`.  This is C-Blosc's
+    /// default, and is compatible with C-Blosc versions older than 1.14.
+    ForwardCompat = BLOSC_FORWARD_COMPAT_SPLIT as i32,
+}
+
+/// The order of the bytes within each element, for
+/// [`Context::big_endian`] and [`Context::little_endian`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ByteOrder {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.  That's the native order of x86 and most
+    /// ARM systems.
+    Little,
+}
+
+impl ByteOrder {
+    /// Is this the host's own byte order?
+    const fn is_native(self) -> bool {
+        match self {
+            ByteOrder::Big => cfg!(target_endian = "big"),
+            ByteOrder::Little => cfg!(target_endian = "little"),
+        }
+    }
+}
+
+/// Reverse the bytes of each `typesize`-byte element of `bytes`.
+///
+/// Any partial element at the end is left alone, just as the shuffle filter
+/// leaves it unshuffled.
+fn swap_elements(bytes: &mut [u8], typesize: usize) {
+    if typesize > 1 {
+        for elem in bytes.chunks_exact_mut(typesize) {
+            elem.reverse();
+        }
+    }
+}
+
+/// Checksum algorithms that can be appended to compressed buffers, with
+/// [`Context::with_checksum`].
+///
+/// # Compatibility
+///
+/// Blosc 1 frames have no place for a checksum, so this is a nonstandard
+/// extension of this crate.  The checksum covers the entire Blosc frame, and
+/// is stored after its end, in little-endian order.  Other Blosc readers will
+/// ignore it if they decompress using the frame's own length.  But
+/// [`validate`] and `blosc_cbuffer_validate` will reject a checksummed buffer
+/// unless the trailer is sliced off first.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Checksum {
+    /// CRC-32C (Castagnoli), stored in 4 bytes.
+    Crc32c,
+}
+
+impl Checksum {
+    /// Identify a checksum by the length of its trailer.
+    fn from_len(len: usize) -> Option<Self> {
+        match len {
+            4 => Some(Checksum::Crc32c),
+            _ => None,
+        }
+    }
+
+    /// Compute the trailer for a Blosc frame.
+    fn compute(self, frame: &[u8]) -> [u8; 4] {
+        match self {
+            Checksum::Crc32c => crc32c::crc32c(frame).to_le_bytes(),
+        }
+    }
+
+    /// The length of the trailer, in bytes.
+    const fn len(self) -> usize {
+        match self {
+            Checksum::Crc32c => 4,
+        }
+    }
+}
+
+/// How much a [`Warning`] is likely to matter.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The settings are probably fine, but may be worth a second look.
+    Info,
+    /// The settings will probably give poor results.
+    Warning,
+}
+
+/// A non-fatal problem with a `Context`'s settings, found by
+/// [`Context::validate`], [`Context::lint`], or [`Context::lint_data`].  Its
+/// `Display` impl gives a human-readable message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// Bit shuffle was requested with a typesize that isn't a power of two.
+    /// Compression ratios may be poor.
+    BitshuffleTypesize(usize),
+    /// The typesize, the first field, doesn't evenly divide the size of the
+    /// array's elements, the second field.  Shuffle will mix up bytes from
+    /// different parts of each element, so compression ratios may be poor.
+    TypesizeMismatch(usize, usize),
+    /// The compressor has no compression levels of its own, so every level
+    /// other than `Clevel::None` compresses the same way.  The level will
+    /// still influence the automatic blocksize, if any.
+    ClevelIgnored(Compressor),
+    /// A shuffle filter was requested, but the typesize, the field, is larger
+    /// than [`max_typesize`].  C-Blosc won't shuffle at all, so compression
+    /// ratios may be poor.
+    TypesizeTooLarge(usize),
+    /// The forced blocksize, the first field, is smaller than the typesize,
+    /// the second field.  C-Blosc doesn't shuffle such blocks at all.
+    BlocksizeTooSmall(usize, usize),
+    /// Shuffle is disabled, but the typesize, the field, looks like that of
+    /// a numeric type.  Numeric arrays usually compress better when
+    /// shuffled.
+    NoShuffle(usize),
+    /// The data looks like it was already shuffled with the typesize, the
+    /// field, so shuffling it again may worsen compression.  This is only a
+    /// guess; see [`Context::lint_data`].
+    LikelyPreshuffled(usize),
+}
+
+impl Warning {
+    /// Return how much this warning is likely to matter.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Warning::NoShuffle(_) | Warning::LikelyPreshuffled(_) => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::BitshuffleTypesize(typesize) => write!(
+                f,
+                "bitshuffle with a typesize of {} that is not a power of two",
+                typesize
+            ),
+            Warning::TypesizeMismatch(typesize, elem_size) => write!(
+                f,
+                "typesize of {} does not divide the element size of {}",
+                typesize, elem_size
+            ),
+            Warning::ClevelIgnored(compressor) => {
+                write!(f, "compression level is ignored by {}", compressor.name())
+            }
+            Warning::BlocksizeTooSmall(blocksize, typesize) => write!(
+                f,
+                "blocksize of {} is smaller than the typesize of {}",
+                blocksize, typesize
+            ),
+            Warning::TypesizeTooLarge(typesize) => write!(
+                f,
+                "typesize of {} is too large to shuffle; the maximum is {}",
+                typesize,
+                max_typesize()
+            ),
+            Warning::NoShuffle(typesize) => write!(
+                f,
+                "shuffle is disabled for a numeric-looking typesize of {}",
+                typesize
+            ),
+            Warning::LikelyPreshuffled(typesize) => write!(
+                f,
+                "data looks already shuffled with a typesize of {}",
+                typesize
+            ),
+        }
+    }
 }
 
 /// Holds basic settings for `compress` operations.
+///
+/// # Environment variables
+///
+/// C-Blosc's simple API honors several environment variables that override
+/// the caller's settings: `BLOSC_CLEVEL`, `BLOSC_SHUFFLE`, `BLOSC_TYPESIZE`,
+/// `BLOSC_COMPRESSOR`, `BLOSC_BLOCKSIZE`, `BLOSC_NTHREADS`, `BLOSC_SPLITMODE`,
+/// and `BLOSC_NOLOCK`.  This crate always uses C-Blosc's context API instead,
+/// which ignores them, so a `Context`'s settings are exactly what will be
+/// used.  The only variable that has any effect is `BLOSC_WARN`, which just
+/// controls whether C-Blosc prints warnings to stderr.
+///
+/// The settings actually used for a buffer can be checked with
+/// [`Context::from_buffer`].
+///
+/// # Threads
+///
+/// Compression and decompression always run on the calling thread, with
+/// C-Blosc's internal thread count fixed at one.  C-Blosc's context API would
+/// spawn and join a fresh set of worker threads on every call if asked for
+/// more, which costs more than it saves for all but the largest buffers.  So
+/// no threads are started per call, and there is no pool to keep warm.
+///
+/// To use several cores, compress independent pieces on threads of your own,
+/// for example with [`Context::compress_into_parts`] and a thread pool.  A
+/// `Context` is `Copy`, `Send`, and `Sync`, so it can be shared freely.
 // LCOV_EXCL_START
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Context {
+    auto_small: usize,
     blocksize: usize,
+    byte_order: Option<ByteOrder>,
+    checksum: Option<Checksum>,
     clevel: Clevel,
     compressor: Compressor,
+    dest_reserve_hint: Option<usize>,
+    shrink: bool,
     shuffle_mode: ShuffleMode,
+    split_mode: Option<SplitMode>,
+    target_blocks: Option<usize>,
     typesize: Option<usize>,
 }
 // LCOV_EXCL_STOP
 
+/// The flags from a Blosc buffer's header, which describe how it was
+/// compressed.
+///
+/// Get them with [`Buffer::flags`] or [`flags`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BufferFlags(u8);
+
+impl BufferFlags {
+    /// Was the buffer shuffled bitwise?
+    pub const fn bit_shuffle(self) -> bool {
+        self.0 & BLOSC_DOBITSHUFFLE as u8 != 0
+    }
+
+    /// Return the raw flag bits.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Was the buffer shuffled bytewise?
+    pub const fn byte_shuffle(self) -> bool {
+        self.0 & BLOSC_DOSHUFFLE as u8 != 0
+    }
+
+    /// Is the buffer a "pure memcpy"?  That is, did C-Blosc give up on
+    /// compression and simply copy the data?  That happens for incompressible
+    /// and very small inputs.
+    pub const fn memcpyed(self) -> bool {
+        self.0 & BLOSC_MEMCPYED as u8 != 0
+    }
+
+    /// Were the buffer's blocks split by byte before compression?  See
+    /// [`SplitMode`].
+    pub const fn split(self) -> bool {
+        self.0 & BLOSC_DONT_SPLIT == 0
+    }
+}
+
+/// Measurements of a single compression, from
+/// [`Context::compress_with_stats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CompressStats {
+    /// Size of the uncompressed data, in bytes.
+    pub uncompressed_bytes: usize,
+    /// Size of the compressed buffer, in bytes, including the header and any
+    /// checksum.
+    pub compressed_bytes: usize,
+    /// `uncompressed_bytes / compressed_bytes`.  Higher is better.
+    pub ratio: f64,
+    /// Whether C-Blosc gave up on compression and simply copied the data.
+    /// That happens for incompressible and very small inputs.
+    pub memcpyed: bool,
+}
+
+/// Measurements of a single compression, from
+/// [`Context::compress_instrumented`].  Requires the `metrics` feature.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Timing {
+    /// Wall-clock time spent compressing, including any checksum.
+    pub elapsed: std::time::Duration,
+    /// Size of the uncompressed data, in bytes.
+    pub bytes_in: usize,
+    /// Size of the compressed buffer, in bytes, including the header and any
+    /// checksum.
+    pub bytes_out: usize,
+}
+
+/// Which SIMD implementations of C-Blosc's shuffle filters are available, from
+/// [`simd_info`].
+///
+/// C-Blosc picks an implementation at runtime, using the fastest one that was
+/// both compiled in and is supported by the CPU.  Otherwise it uses a generic
+/// implementation, which is much slower.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SimdInfo {
+    /// Whether C-Blosc was built with its SSE2 shuffle, or `None` if unknown.
+    pub sse2_compiled: Option<bool>,
+    /// Whether C-Blosc was built with its AVX2 shuffle, or `None` if unknown.
+    pub avx2_compiled: Option<bool>,
+    /// Whether this CPU supports SSE2.
+    pub sse2_cpu: bool,
+    /// Whether this CPU supports AVX2.
+    pub avx2_cpu: bool,
+}
+
+/// How [`Buffer::to_framed`] serializes a buffer.
+///
+/// Length prefixes count only the buffer itself, and are little-endian.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Framing {
+    /// Just the Blosc buffer.  It records its own length, so no prefix is
+    /// needed to read it, but it can't be skipped over without parsing it.
+    Raw,
+    /// The buffer's length as a `u32`, followed by the buffer.
+    LengthPrefixedU32,
+    /// The buffer's length as a `u64`, followed by the buffer.  This is the
+    /// format of [`Buffer::write_to`].
+    LengthPrefixedU64,
+}
+
+impl Framing {
+    /// The length of the prefix, in bytes.
+    const fn prefix_len(self) -> usize {
+        match self {
+            Framing::Raw => 0,
+            Framing::LengthPrefixedU32 => mem::size_of::<u32>(),
+            Framing::LengthPrefixedU64 => mem::size_of::<u64>(),
+        }
+    }
+}
+
 /// An opaque Blosc-compressed buffer.
 ///
 /// It can be safely decompressed back into an array of the original type.
+///
+/// A `Buffer<T>` is `Send` and `Sync` only when `T` is.  It holds only bytes,
+/// but decompressing it recreates the original `T` values, possibly on
+/// another thread.
+///
+/// # Compatibility
+///
+/// The bytes are a standard Blosc 1 buffer, unless a [`Checksum`] was added.
+/// C-Blosc2 can decompress Blosc 1 buffers directly, as a single chunk, with
+/// `blosc2_decompress` or Python's `blosc2.decompress`.  This crate can't
+/// write Blosc2's frame format, which is needed by Blosc2's super-chunk APIs.
 pub struct Buffer<T> {
     data: Vec<u8>,
+    /// Number of elements of the original array
+    nelem: usize,
     phantom: PhantomData<T>,
 }
 
 impl<T> Buffer<T> {
-    fn from_vec(src: Vec<u8>) -> Self {
+    fn from_vec(src: Vec<u8>, nelem: usize) -> Self {
         Buffer {
             data: src,
+            nelem,
             phantom: PhantomData,
         }
     }
 
-    /// Return the size of the compressed buffer.
-    pub fn size(&self) -> usize {
-        self.data.len()
+    /// Discard the buffer's contents, but keep its allocation.
+    ///
+    /// A cleared buffer can be refilled with [`Context::compress_reuse`].
+    /// Until then, attempting to decompress it will return an error.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.nelem = 0;
     }
-}
 
-impl<T> AsRef<[u8]> for Buffer<T> {
-    fn as_ref(&self) -> &[u8] {
-        self.data.as_ref()
+    /// Return the compressed buffer as a byte slice.
+    ///
+    /// This is the same as [`AsRef::as_ref`].
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..]
     }
-}
 
-impl<T> Hash for Buffer<T> {
-    fn hash<H: Hasher>(&self, hasher: &mut H) {
-        hasher.write(self.as_ref());
+    /// Borrow the buffer as a [`BufferRef`].
+    pub fn as_ref_buffer(&self) -> BufferRef<'_, T> {
+        BufferRef {
+            data: &self.data[..],
+            nelem: self.nelem,
+            phantom: PhantomData,
+        }
     }
-}
 
-impl<T> From<Buffer<T>> for Vec<u8> {
-    /// Transform `self` into a raw `Vec` of bytes.  After this, it can no
-    /// longer be safely decompressed.
-    fn from(buf: Buffer<T>) -> Self {
-        buf.data
+    /// Return the blocksize that C-Blosc used to compress this buffer.
+    ///
+    /// This is most interesting when the `Context`'s blocksize was left
+    /// automatic.
+    pub fn blocksize(&self) -> usize {
+        self.sizes().2
     }
-}
 
-impl Context {
-    /// Select the `Context`'s blocksize.
+    /// Compute a cryptographic digest of the compressed buffer.
     ///
-    /// Blocksize is the amount of data the compressor will work on at one time.
-    /// Limiting it can improve the CPU's cache hit rate.  Increasing it can
-    /// improve compression.  Generally this should be `None`, in which case
-    /// Blosc will choose a sensible value.
-    pub fn blocksize(mut self, blocksize: Option<usize>) -> Self {
-        self.blocksize = blocksize.unwrap_or(0);
-        self
+    /// Any hash function implementing [`digest::Digest`] may be used.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// use sha2::Sha256;
+    /// let a = Context::new().compress(&[1u32, 2, 3][..]);
+    /// let b = Context::new().compress(&[1u32, 2, 3][..]);
+    /// assert_eq!(a.digest::<Sha256>(), b.digest::<Sha256>());
+    /// ```
+    #[cfg(feature = "digest")]
+    pub fn digest<D: ::digest::Digest>(&self) -> ::digest::Output<D> {
+        D::digest(self.as_ref())
     }
 
-    /// Select the `Context`'s compression level.
+    /// Return the compressor that actually produced this buffer, according to
+    /// its header.
     ///
-    /// Higher values will give better compression at the expense of speed.
-    pub const fn clevel(mut self, clevel: Clevel) -> Self {
-        self.clevel = clevel;
-        self
+    /// C-Blosc's `BLOSC_COMPRESSOR` environment variable can't override a
+    /// `Context`'s compressor (see [`Context`]), but this may still differ
+    /// from the configured one: the header only records the compression
+    /// library, so `LZ4HC` buffers are reported as `LZ4`.
+    ///
+    /// Returns [`BloscError::ValidationError`] if the buffer has been cleared,
+    /// or [`BloscError::CompressorNotSupported`] if the header names an
+    /// unknown compression library.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new().compressor(Compressor::LZ4HC).unwrap();
+    /// let compressed = ctx.compress(&[1u32, 2, 3][..]);
+    /// assert_eq!(Ok(Compressor::LZ4), compressed.effective_compressor());
+    /// ```
+    pub fn effective_compressor(&self) -> Result<Compressor> {
+        if self.data.len() < BLOSC_MIN_HEADER_LENGTH as usize {
+            return Err(BloscError::ValidationError);
+        }
+        Compressor::from_header(&self.data[..]).ok_or(BloscError::CompressorNotSupported)
     }
 
-    /// Select the `Context`'s compression algorithm.
+    /// Read a buffer written by [`write_to`](Buffer::write_to).
     ///
-    /// Returns an error if the `compressor` is not enabled in this build of
-    /// C-Blosc.
-    pub fn compressor(mut self, compressor: Compressor) -> Result<Self> {
-        let comp_ptr: *const c_char = compressor.into();
-        let support = unsafe { blosc_get_complib_info(comp_ptr, ptr::null_mut(), ptr::null_mut()) };
-        if support >= 0 {
-            self.compressor = compressor;
-            Ok(self)
-        } else {
-            Err(BloscError::CompressorNotSupported)
+    /// The element count is inferred from the header.  Fails with
+    /// `io::ErrorKind::InvalidData` if the data doesn't look like a Blosc
+    /// buffer of `T`.
+    ///
+    /// # Safety
+    ///
+    /// The data is only superficially checked, so the same requirements apply
+    /// as for [`decompress_bytes`].
+    pub unsafe fn read_from<R: Read>(r: &mut R) -> io::Result<Buffer<T>> {
+        let mut len = [0u8; mem::size_of::<u64>()];
+        r.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len);
+        let mut data = Vec::new();
+        // Don't trust the length enough to preallocate it
+        r.take(len).read_to_end(&mut data)?;
+        if (data.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
         }
+        Self::from_bytes(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
     }
 
-    /// Compress an array and return a newly allocated compressed buffer.
-    pub fn compress<T>(&self, src: &[T]) -> Buffer<T> {
-        let typesize = self.typesize.unwrap_or(mem::size_of::<T>());
-        let src_size = mem::size_of_val(src);
-        let dest_size = src_size + BLOSC_MAX_OVERHEAD as usize;
-        let mut dest: Vec<u8> = Vec::with_capacity(dest_size);
-        let rsize = unsafe {
-            blosc_compress_ctx(
-                self.clevel as c_int,
-                self.shuffle_mode as c_int,
-                typesize,
-                src_size,
-                src.as_ptr() as *const c_void,
-                dest.as_mut_ptr() as *mut c_void,
-                dest_size,
-                self.compressor.into(),
-                self.blocksize,
-                1,
-            )
-        };
-        // Blosc's docs claim that blosc_compress_ctx should never return an
-        // error
-        // LCOV_EXCL_START
-        assert!(
-            rsize >= 0,
-            "C-Blosc internal error with Context={:?}, typesize={:?} nbytes={:?} and destsize={:?}",
-            self,
-            typesize,
-            src_size,
-            dest_size
-        );
-        // LCOV_EXCL_STOP
-        unsafe {
-            dest.set_len(rsize as usize);
+    /// Wrap serialized bytes in a `Buffer`, after superficially checking them.
+    unsafe fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let (nbytes, cbytes, _blocksize) = cbuffer_sizes(&data[..])?;
+        if cbytes > data.len() {
+            return Err(BloscError::ValidationError);
         }
-        dest.shrink_to_fit();
-        Buffer::from_vec(dest)
+        let typesize = mem::size_of::<T>();
+        if typesize > 0 && nbytes % typesize != 0 {
+            return Err(BloscError::TypesizeMismatch);
+        }
+        let nelem = nbytes.checked_div(typesize).unwrap_or(0);
+        Ok(Buffer::from_vec(data, nelem))
     }
 
-    /// Build a default compression context.
+    /// Read a buffer serialized by [`to_framed`](Buffer::to_framed).
     ///
-    /// # Example
+    /// `src` must hold exactly one frame.  Returns [`BloscError::Truncated`]
+    /// if it's shorter than its length prefix claims, or
+    /// [`BloscError::ValidationError`] if it's longer or doesn't look like a
+    /// Blosc buffer.
     ///
-    /// ```
-    /// # use blosc::*;
-    /// # #[allow(unused)]
-    /// let ctx = Context::new()
-    ///     .blocksize(Some(262144))
-    ///     .compressor(Compressor::Zstd).unwrap()
-    ///     .clevel(Clevel::L9)
-    ///     .shuffle(ShuffleMode::Bit);
-    /// ```
-    pub const fn new() -> Self {
-        Context {
-            blocksize: 0,                    // Automatic blocksize
-            clevel: Clevel::L2,              // Level 2 selects blocksize to equal L1 cache
-            compressor: Compressor::BloscLZ, // Default algorithm
-            shuffle_mode: ShuffleMode::None, // Don't shuffle by default
-            typesize: None,                  // autodetect by default
+    /// # Safety
+    ///
+    /// The data is only superficially checked, so the same requirements apply
+    /// as for [`decompress_bytes`].
+    pub unsafe fn from_framed(src: &[u8], framing: Framing) -> Result<Buffer<T>> {
+        let prefix_len = framing.prefix_len();
+        if src.len() < prefix_len {
+            return Err(BloscError::Truncated);
         }
+        let (prefix, data) = src.split_at(prefix_len);
+        let mut len = [0u8; 8];
+        len[..prefix_len].copy_from_slice(prefix);
+        let len = match framing {
+            Framing::Raw => data.len() as u64,
+            _ => u64::from_le_bytes(len),
+        };
+        if (data.len() as u64) < len {
+            return Err(BloscError::Truncated);
+        } else if (data.len() as u64) > len {
+            return Err(BloscError::ValidationError);
+        }
+        Self::from_bytes(data.to_vec())
     }
 
-    /// Select which Shuffle filter to apply before compression.
-    pub const fn shuffle(mut self, shuffle_mode: ShuffleMode) -> Self {
-        self.shuffle_mode = shuffle_mode;
-        self
+    /// Return the flags from the buffer's header.
+    ///
+    /// Returns an error if the buffer has been cleared.
+    pub fn flags(&self) -> Result<BufferFlags> {
+        flags(&self.data[..])
     }
 
-    /// Manually set the size in bytes to assume for each uncompressed array
-    /// element.
+    /// Return `true` if the buffer has no contents, which happens after
+    /// [`clear`](Buffer::clear).
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Return the size of the compressed buffer, in bytes.
     ///
-    /// The `typesize` is used for Blosc's shuffle operation.  When compressing
-    /// arrays, the `typesize` should be the size of each array element.  If
-    /// `None` or unspecified, it will be autodetected.  However, manually
-    /// setting `typesize` can be useful when compressing preserialized buffers
-    /// or single structures that contain arrays.
+    /// This is the same as [`size`](Buffer::size).
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return the size of the compressed buffer.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check that the buffer may contain valid Blosc compressed data, like
+    /// [`validate`].  On success, returns the size that the uncompressed data
+    /// would have.
+    ///
+    /// Unlike [`validate`], this also accepts a buffer with a checksum, after
+    /// verifying it.  Like [`validate`], it only checks the header.  It does
+    /// not make it safe to decompress an untrusted buffer.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    /// let compressed = ctx.compress(&[1u16, 2, 3, 65535][..]);
+    /// assert_eq!(Ok(8), compressed.validate());
+    /// ```
+    pub fn validate(&self) -> Result<usize> {
+        validate(checked_frame(&self.data[..])?)
+    }
+
+    /// Serialize the buffer with the chosen framing.
+    ///
+    /// Read it back with [`from_framed`](Buffer::from_framed).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is too long for the framing's length prefix.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+    /// let framed = compressed.to_framed(Framing::LengthPrefixedU32);
+    /// assert_eq!(compressed.size() + 4, framed.len());
+    /// let buf = unsafe { Buffer::<u32>::from_framed(&framed[..], Framing::LengthPrefixedU32) };
+    /// assert_eq!(vec![1, 2, 3], decompress(&buf.unwrap()).unwrap());
+    /// ```
+    pub fn to_framed(&self, framing: Framing) -> Vec<u8> {
+        let len = self.data.len() as u64;
+        let mut framed = Vec::with_capacity(framing.prefix_len() + self.data.len());
+        match framing {
+            Framing::Raw => (),
+            Framing::LengthPrefixedU32 => {
+                let len = u32::try_from(len).expect("Buffer too long for a u32 length prefix");
+                framed.extend_from_slice(&len.to_le_bytes());
+            }
+            Framing::LengthPrefixedU64 => framed.extend_from_slice(&len.to_le_bytes()),
+        }
+        framed.extend_from_slice(&self.data[..]);
+        framed
+    }
+
+    /// Write the buffer with a length prefix, so several can be stored in
+    /// one stream.
+    ///
+    /// The format is the buffer's length in bytes, as a little-endian `u64`,
+    /// followed by the buffer itself.  Read it back with
+    /// [`read_from`](Buffer::read_from).
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new();
+    /// let mut stream = Vec::new();
+    /// ctx.compress(&[1u32, 2, 3][..]).write_to(&mut stream).unwrap();
+    /// ctx.compress(&[4u32, 5][..]).write_to(&mut stream).unwrap();
+    ///
+    /// let mut reader = &stream[..];
+    /// let a = unsafe { Buffer::<u32>::read_from(&mut reader) }.unwrap();
+    /// let b = unsafe { Buffer::<u32>::read_from(&mut reader) }.unwrap();
+    /// assert_eq!(vec![1, 2, 3], decompress(&a).unwrap());
+    /// assert_eq!(vec![4, 5], decompress(&b).unwrap());
+    /// ```
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&(self.data.len() as u64).to_le_bytes())?;
+        w.write_all(&self.data[..])
+    }
+
+    /// Read the `(nbytes, cbytes, blocksize)` fields from the header.
+    fn sizes(&self) -> (usize, usize, usize) {
+        // A Buffer's header is complete unless it has been cleared
+        cbuffer_sizes(&self.data[..]).unwrap_or((0, 0, 0))
+    }
+}
+
+impl<T: Copy> Buffer<T> {
+    /// Recompress the buffer with `ctx`, and return whichever of the two is
+    /// smaller.
+    ///
+    /// This suits trying a heavier compressor on cold data, keeping it only if
+    /// it helps.  If the recompressed buffer isn't strictly smaller, a copy of
+    /// the original is returned.  Any checksum is verified while
+    /// decompressing.  `ctx` should store elements in the same byte order as
+    /// the original, or the elements will appear byte-swapped.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..10_000).collect();
+    /// let original = Context::new().clevel(Clevel::None).compress(&data[..]);
+    /// let heavy = Context::ZSTD_BITSHUFFLE_L9;
+    /// let smaller = original.recompress_if_better(&heavy).unwrap();
+    /// assert!(smaller.size() < original.size());
+    /// let same = smaller.recompress_if_better(&Context::new()).unwrap();
+    /// assert_eq!(smaller.size(), same.size());
+    /// assert_eq!(data, decompress(&same).unwrap());
+    /// ```
+    pub fn recompress_if_better(&self, ctx: &Context) -> Result<Buffer<T>> {
+        let recompressed = ctx.compress(&decompress(self)?[..]);
+        if recompressed.size() < self.size() {
+            Ok(recompressed)
+        } else {
+            Ok(Buffer::from_vec(self.data.clone(), self.nelem))
+        }
+    }
+
+    /// Recompress the buffer with a different shuffle mode, keeping its other
+    /// settings.
+    ///
+    /// The other settings come from [`Context::from_buffer`], with the same
+    /// limitations.  In particular the compression level isn't recorded, so
+    /// it will be `Clevel::L2`.  To control every setting, decompress the
+    /// buffer once and compress it with each `Context` instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..10_000).collect();
+    /// let ctx = Context::new().compressor(Compressor::LZ4).unwrap();
+    /// let unshuffled = ctx.compress(&data[..]);
+    /// let shuffled = unshuffled.with_shuffle(ShuffleMode::Byte).unwrap();
+    /// assert!(shuffled.flags().unwrap().byte_shuffle());
+    /// assert_eq!(Ok(Compressor::LZ4), shuffled.effective_compressor());
+    /// assert!(shuffled.size() < unshuffled.size());
+    /// ```
+    pub fn with_shuffle(&self, mode: ShuffleMode) -> Result<Buffer<T>> {
+        let ctx = Context::from_buffer(self)?.shuffle(mode);
+        Ok(ctx.compress(&decompress(self)?[..]))
+    }
+}
+
+impl<T: Plain> Buffer<T> {
+    /// Change the buffer's element type, without recompressing it.
+    ///
+    /// Both types must be [`Plain`], so every bit pattern is valid for `U`.
+    /// The element count is recomputed from the uncompressed size, so `U`
+    /// needn't have the same size as `T`.  Returns
+    /// [`BloscError::TypesizeMismatch`] if the uncompressed size isn't a
+    /// multiple of `U`'s size, or if `U` is zero-sized and the buffer isn't
+    /// empty.  The header's typesize is unchanged, so the
+    /// shuffle filter is still undone correctly.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let compressed = Context::new().compress(&[1u32, 2][..]);
+    /// let bytes = compressed.map_type::<[u8; 4]>().unwrap();
+    /// assert_eq!(vec![1u32.to_ne_bytes(), 2u32.to_ne_bytes()], decompress(&bytes).unwrap());
+    /// ```
+    pub fn map_type<U: Plain>(self) -> Result<Buffer<U>> {
+        let nbytes = self.nelem * mem::size_of::<T>();
+        let size = mem::size_of::<U>();
+        let nelem = match nbytes.checked_rem(size) {
+            Some(0) => nbytes / size,
+            Some(_) => return Err(BloscError::TypesizeMismatch),
+            // U is zero-sized
+            None if nbytes == 0 => 0,
+            None => return Err(BloscError::TypesizeMismatch),
+        };
+        Ok(Buffer::from_vec(self.data, nelem))
+    }
+}
+
+impl<T> AsRef<[u8]> for Buffer<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+}
+
+/// Summarize the buffer for human readers, like
+/// `"3.8 MiB → 22 KiB (178.1x, zstd, bitshuffle)"`.
+///
+/// Everything in the summary comes from the buffer's header.
+impl<T> fmt::Display for Buffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = match self.flags() {
+            Ok(flags) => flags,
+            // The Buffer has been cleared
+            Err(_) => return write!(f, "empty"),
+        };
+        let compressor = Compressor::from_header(&self.data[..]);
+        let nbytes = self.sizes().0;
+        let shuffle = match ShuffleMode::from_flags(flags) {
+            ShuffleMode::Bit => "bitshuffle",
+            ShuffleMode::Byte => "shuffle",
+            _ => "noshuffle",
+        };
+        write!(
+            f,
+            "{} → {} ({:.1}x, {}, {})",
+            IecBytes(nbytes),
+            IecBytes(self.data.len()),
+            nbytes as f64 / self.data.len() as f64,
+            compressor.map(Compressor::name).unwrap_or("unknown"),
+            shuffle
+        )
+    }
+}
+
+impl<T> Hash for Buffer<T> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(self.as_ref());
+    }
+}
+
+/// Buffers are equal if their compressed bytes are equal.
+///
+/// The same data compressed with different settings is not equal.
+impl<T> PartialEq for Buffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T> Eq for Buffer<T> {}
+
+/// Buffers are ordered lexicographically by their compressed bytes, consistent
+/// with `Eq`.
+///
+/// The order says nothing about the uncompressed data, but it's deterministic,
+/// so buffers can be used as keys in a `BTreeMap`.
+impl<T> Ord for Buffer<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+
+impl<T> PartialOrd for Buffer<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Safe because a Buffer contains only a Vec<u8>, and decompressing it can only
+// produce T values on a thread where T could have been sent or shared anyway.
+unsafe impl<T: Send> Send for Buffer<T> {}
+unsafe impl<T: Sync> Sync for Buffer<T> {}
+
+impl<T> From<&[T]> for Buffer<T> {
+    /// Compress `src` with the default [`Context`].
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let compressed = Buffer::from(&[1u32, 2, 3][..]);
+    /// assert_eq!(vec![1, 2, 3], decompress(&compressed).unwrap());
+    /// ```
+    fn from(src: &[T]) -> Self {
+        Context::new().compress(src)
+    }
+}
+
+impl<T> From<Buffer<T>> for Vec<u8> {
+    /// Transform `self` into a raw `Vec` of bytes.  After this, it can no
+    /// longer be safely decompressed.
+    fn from(buf: Buffer<T>) -> Self {
+        buf.data
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Types with no padding, for which every bit pattern is valid, so
+/// [`Buffer::map_type`] can convert between them.
+///
+/// It's implemented for the primitive numeric types, and arrays of them.  It
+/// can't be implemented outside of this crate.
+pub trait Plain: Copy + sealed::Sealed {}
+
+macro_rules! impl_plain {
+    ($($t:ty)*) => {
+        $(
+            impl sealed::Sealed for $t {}
+            impl Plain for $t {}
+        )*
+    };
+}
+
+impl_plain! {u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64}
+
+impl<P: Plain, const N: usize> sealed::Sealed for [P; N] {}
+impl<P: Plain, const N: usize> Plain for [P; N] {}
+
+/// A Blosc-compressed array borrowed from somewhere else, like a
+/// memory-mapped file.
+///
+/// This is the borrowing counterpart of [`Buffer`]: it can be decompressed
+/// and inspected the same way, but doesn't own its bytes, so they needn't be
+/// copied first.  Borrow an owned `Buffer` with
+/// [`Buffer::as_ref_buffer`].
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// let bytes: &[u8] = compressed.as_ref();
+/// let borrowed = unsafe { BufferRef::<u32>::from_bytes(bytes) }.unwrap();
+/// assert_eq!(vec![1, 2, 3], borrowed.decompress().unwrap());
+/// assert_eq!(vec![1], borrowed.decompress_prefix(1).unwrap());
+/// ```
+pub struct BufferRef<'a, T> {
+    data: &'a [u8],
+    /// Number of elements of the original array
+    nelem: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<'a, T> BufferRef<'a, T> {
+    /// Return the compressed buffer as a byte slice.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return the blocksize that C-Blosc used to compress this buffer, like
+    /// [`Buffer::blocksize`].
+    pub fn blocksize(&self) -> usize {
+        self.sizes().2
+    }
+
+    /// Return the compressor that produced this buffer, like
+    /// [`Buffer::effective_compressor`].
+    pub fn effective_compressor(&self) -> Result<Compressor> {
+        if self.data.len() < BLOSC_MIN_HEADER_LENGTH as usize {
+            return Err(BloscError::ValidationError);
+        }
+        Compressor::from_header(self.data).ok_or(BloscError::CompressorNotSupported)
+    }
+
+    /// Return the flags from the buffer's header.
+    pub fn flags(&self) -> Result<BufferFlags> {
+        flags(self.data)
+    }
+
+    /// Borrow serialized bytes as a `BufferRef`, after superficially checking
+    /// them.
+    ///
+    /// The element count is inferred from the header.  Returns
+    /// [`BloscError::ValidationError`] if `src` doesn't look like a Blosc
+    /// buffer, or [`BloscError::TypesizeMismatch`] if its length isn't a
+    /// multiple of `T`'s size.  `src` must hold exactly one buffer, including
+    /// its checksum if it has one.
+    ///
+    /// # Safety
+    ///
+    /// The data is only superficially checked, so the same requirements apply
+    /// as for [`decompress_bytes`].
+    pub unsafe fn from_bytes(src: &'a [u8]) -> Result<Self> {
+        let (nbytes, cbytes, _blocksize) = cbuffer_sizes(src)?;
+        if cbytes > src.len() {
+            return Err(BloscError::ValidationError);
+        }
+        let typesize = mem::size_of::<T>();
+        if typesize > 0 && nbytes % typesize != 0 {
+            return Err(BloscError::TypesizeMismatch);
+        }
+        Ok(BufferRef {
+            data: src,
+            nelem: nbytes.checked_div(typesize).unwrap_or(0),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Return `true` if the buffer has no contents.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Return the size of the compressed buffer, in bytes.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return the size of the compressed buffer.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check the buffer's header, and its checksum if it has one, like
+    /// [`Buffer::validate`].
+    pub fn validate(&self) -> Result<usize> {
+        validate(checked_frame(self.data)?)
+    }
+
+    /// Read the `(nbytes, cbytes, blocksize)` fields from the header.
+    fn sizes(&self) -> (usize, usize, usize) {
+        cbuffer_sizes(self.data).unwrap_or((0, 0, 0))
+    }
+}
+
+impl<'a, T: Copy> BufferRef<'a, T> {
+    /// Decompress the buffer, like [`decompress`].
+    pub fn decompress(&self) -> Result<Vec<T>> {
+        unsafe { decompress_nelem(checked_frame(self.data)?, self.nelem) }
+    }
+
+    /// Decompress only the first `n` elements, like [`decompress_prefix`].
+    pub fn decompress_prefix(&self, n: usize) -> Result<Vec<T>> {
+        decompress_prefix_ref(*self, n)
+    }
+}
+
+impl<'a, T> AsRef<[u8]> for BufferRef<'a, T> {
+    fn as_ref(&self) -> &[u8] {
+        self.data
+    }
+}
+
+impl<'a, T> Clone for BufferRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for BufferRef<'a, T> {}
+
+// Safe for the same reasons as Buffer's impls.
+unsafe impl<'a, T: Send> Send for BufferRef<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for BufferRef<'a, T> {}
+
+/// A Blosc-compressed array of variable-length rows.
+///
+/// The row lengths are stored in a small uncompressed prefix, followed by an
+/// ordinary Blosc buffer containing every row's elements.  Create one with
+/// [`Context::compress_ragged`] and decompress it with [`decompress_ragged`].
+pub struct RaggedBuffer<T> {
+    data: Vec<u8>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> RaggedBuffer<T> {
+    /// Return the size of the compressed buffer, including the row lengths.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl<T> AsRef<[u8]> for RaggedBuffer<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+}
+
+impl<T> From<RaggedBuffer<T>> for Vec<u8> {
+    /// Transform `self` into a raw `Vec` of bytes.  After this, it can no
+    /// longer be safely decompressed.
+    fn from(buf: RaggedBuffer<T>) -> Self {
+        buf.data
+    }
+}
+
+/// Describes the fields of a struct, so that [`Context::compress_soa`] can
+/// compress each one separately.
+///
+/// A single typesize can't shuffle a struct with fields of different sizes
+/// well.  Compressing each field as its own column fixes that.  Describe an
+/// array field as several fields, one per element, so each gets the
+/// element's typesize.
+///
+/// # Safety
+///
+/// Each field must lie within `Self`, the fields must not overlap, and
+/// together they must cover every byte of `Self` that isn't padding.  Like
+/// with [`decompress_bytes`], every bit pattern must be valid for each field.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// #[repr(C)]
+/// struct Sample {
+///     value: f64,
+///     channel: u8,
+/// }
+///
+/// unsafe impl Columnar for Sample {
+///     const FIELDS: &'static [(usize, usize)] = &[(0, 8), (8, 1)];
+/// }
+/// ```
+pub unsafe trait Columnar: Copy {
+    /// The offset and size of each field, in bytes.
+    const FIELDS: &'static [(usize, usize)];
+}
+
+/// An array of structs, compressed one field at a time by
+/// [`Context::compress_soa`].
+///
+/// It holds the number of structs, followed by a [`ColumnSet`] blob with one
+/// column per field.  Decompress it with [`decompress_soa`].
+pub struct SoaBuffer<T> {
+    data: Vec<u8>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> SoaBuffer<T> {
+    /// Return the size of the compressed buffer, including every field.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl<T> AsRef<[u8]> for SoaBuffer<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+}
+
+impl<T> From<SoaBuffer<T>> for Vec<u8> {
+    /// Transform `self` into a raw `Vec` of bytes.  After this, it can no
+    /// longer be safely decompressed.
+    fn from(buf: SoaBuffer<T>) -> Self {
+        buf.data
+    }
+}
+
+/// The result of [`Context::compress_or_store`]: either a compressed buffer,
+/// or the original array if compression wouldn't have saved any space.
+pub enum Stored<T> {
+    /// The array was compressed.
+    Compressed(Buffer<T>),
+    /// The array was incompressible, so it's stored as-is.
+    Raw(Vec<T>),
+}
+
+impl<T> Stored<T> {
+    /// Return the size of the stored data, in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Stored::Compressed(buf) => buf.size(),
+            Stored::Raw(v) => mem::size_of_val(&v[..]),
+        }
+    }
+}
+
+impl<T: Copy> IntoIterator for Buffer<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    /// Decompress the buffer and iterate over its elements.
+    ///
+    /// The entire buffer is decompressed eagerly, when `into_iter` is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if decompression fails.  Use [`decompress`] to handle that error
+    /// instead.
+    fn into_iter(self) -> Self::IntoIter {
+        (&self).into_iter()
+    }
+}
+
+impl<T: Copy> IntoIterator for &Buffer<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    /// Decompress the buffer and iterate over its elements.
+    ///
+    /// The entire buffer is decompressed eagerly, when `into_iter` is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if decompression fails.  Use [`decompress`] to handle that error
+    /// instead.
+    fn into_iter(self) -> Self::IntoIter {
+        decompress(self)
+            .expect("Failed to decompress a blosc::Buffer")
+            .into_iter()
+    }
+}
+
+thread_local! {
+    /// Scratch space for [`Context::measure_ratio`].
+    static MEASURE_SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Context {
+    /// Fast compression with LZ4 and byte shuffle, at level 5.
+    ///
+    /// Compression will panic if this build of C-Blosc doesn't support LZ4.
+    pub const LZ4_SHUFFLE_L5: Context = Context::new()
+        .clevel(Clevel::L5)
+        .shuffle(ShuffleMode::Byte)
+        .with_compressor(Compressor::LZ4);
+
+    /// The highest compression, with Zstd and bit shuffle, at level 9.
+    ///
+    /// Compression will panic if this build of C-Blosc doesn't support Zstd.
+    pub const ZSTD_BITSHUFFLE_L9: Context = Context::new()
+        .clevel(Clevel::L9)
+        .shuffle(ShuffleMode::Bit)
+        .with_compressor(Compressor::Zstd);
+
+    /// Skip the shuffle filter for inputs smaller than `threshold` bytes.
+    ///
+    /// Shuffling very small inputs costs time, but rarely improves their
+    /// compression.  By default, or if `threshold` is `None`, the shuffle
+    /// filter is always used as configured.  A `threshold` of about 16 times
+    /// the typesize is a reasonable start.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new()
+    ///     .shuffle(ShuffleMode::Byte)
+    ///     .auto_small(Some(64));
+    /// let compressed = ctx.compress(&[1u32, 2, 3][..]);
+    /// assert!(!compressed.flags().unwrap().byte_shuffle());
+    /// ```
+    pub const fn auto_small(mut self, threshold: Option<usize>) -> Self {
+        self.auto_small = match threshold {
+            Some(threshold) => threshold,
+            None => 0,
+        };
+        self
+    }
+
+    /// Store elements in big-endian order, regardless of the host's byte order.
+    ///
+    /// If the host is little-endian, each element's bytes are reversed before
+    /// compression.  The typesize determines the element size.  Blosc's header
+    /// has no room to record the byte order, so the reader must know to use
+    /// [`decompress_with_byte_order`].
+    ///
+    /// This only makes sense for arrays of primitive numbers.  A struct's
+    /// fields would need to be swapped individually.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let compressed = Context::new().big_endian().compress(&[1u16, 2][..]);
+    /// let raw = unsafe { decompress_bytes::<u8>(compressed.as_ref()) }.unwrap();
+    /// assert_eq!(vec![0, 1, 0, 2], raw);
+    /// let data = unsafe { decompress_with_byte_order(&compressed, ByteOrder::Big) };
+    /// assert_eq!(vec![1, 2], data.unwrap());
+    /// ```
+    pub const fn big_endian(mut self) -> Self {
+        self.byte_order = Some(ByteOrder::Big);
+        self
+    }
+
+    /// Select the `Context`'s blocksize.
+    ///
+    /// Blocksize is the amount of data the compressor will work on at one time.
+    /// Limiting it can improve the CPU's cache hit rate.  Increasing it can
+    /// improve compression.  Generally this should be `None`, in which case
+    /// Blosc will choose a sensible value.  Either way, it's rounded down to a
+    /// multiple of the typesize; see [`SplitMode`].
+    pub fn blocksize(mut self, blocksize: Option<usize>) -> Self {
+        self.blocksize = blocksize.unwrap_or(0);
+        self
+    }
+
+    /// Select the `Context`'s compression level.
+    ///
+    /// Higher values will give better compression at the expense of speed.
+    pub const fn clevel(mut self, clevel: Clevel) -> Self {
+        self.clevel = clevel;
+        self
+    }
+
+    /// Select the `Context`'s compression algorithm.
+    ///
+    /// Returns an error if the `compressor` is not enabled in this build of
+    /// C-Blosc.
+    pub fn compressor(mut self, compressor: Compressor) -> Result<Self> {
+        if compressor.supported() {
+            self.compressor = compressor;
+            Ok(self)
+        } else {
+            Err(BloscError::CompressorNotSupported)
+        }
+    }
+
+    /// Select the `Context`'s compression algorithm by its C-Blosc name, like
+    /// `"zstd"`.
+    ///
+    /// Returns [`BloscError::UnknownCompressor`] if the name isn't recognized,
+    /// or [`BloscError::CompressorNotSupported`] if it is but isn't enabled in
+    /// this build of C-Blosc.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new().compressor_by_name("lz4").unwrap();
+    /// assert_eq!(ctx, Context::new().compressor(Compressor::LZ4).unwrap());
+    /// assert_eq!(
+    ///     Err(BloscError::UnknownCompressor),
+    ///     Context::new().compressor_by_name("lzma")
+    /// );
+    /// ```
+    pub fn compressor_by_name(self, name: &str) -> Result<Self> {
+        self.compressor(name.parse()?)
+    }
+
+    /// Compress an array and return a newly allocated compressed buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` is larger than C-Blosc's limit of just under 2 GiB per
+    /// buffer.  So do the other compression methods, except for
+    /// [`compress_into_slice`](Context::compress_into_slice), which returns
+    /// [`BloscError::SizeLimitExceeded`] instead.
+    pub fn compress<T>(&self, src: &[T]) -> Buffer<T> {
+        let mut dest: Vec<u8> = Vec::new();
+        self.compress_vec(src, &mut dest);
+        if self.shrink {
+            dest.shrink_to_fit();
+        }
+        Buffer::from_vec(dest, src.len())
+    }
+
+    /// Compress an array of `MaybeUninit<T>`, as though it were an array of
+    /// `T`.
+    ///
+    /// This avoids copying an array that was built in place into a `Vec<T>`
+    /// first.
+    ///
+    /// # Safety
+    ///
+    /// Every element of `src` must be fully initialized.  Compressing
+    /// uninitialized memory is undefined behavior, just like reading it.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// # use std::mem::MaybeUninit;
+    /// let mut data = [MaybeUninit::<u32>::uninit(); 3];
+    /// for (i, x) in data.iter_mut().enumerate() {
+    ///     *x = MaybeUninit::new(i as u32);
+    /// }
+    /// let compressed = unsafe { Context::new().compress_assume_init(&data[..]) };
+    /// assert_eq!(vec![0, 1, 2], decompress(&compressed).unwrap());
+    /// ```
+    pub unsafe fn compress_assume_init<T>(&self, src: &[mem::MaybeUninit<T>]) -> Buffer<T> {
+        // MaybeUninit<T> has the same layout as T
+        let src = slice::from_raw_parts(src.as_ptr() as *const T, src.len());
+        self.compress(src)
+    }
+
+    /// Compress an array of `bool`s, packing them eight to a byte first.
+    ///
+    /// The number of `bool`s is recorded in the compressed data, so
+    /// [`decompress_bools`] can restore the exact array.  Bit shuffle works
+    /// well on sparse masks.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let mask: Vec<bool> = (0..100_000).map(|i| i % 100 == 0).collect();
+    /// let ctx = Context::new().shuffle(ShuffleMode::Bit);
+    /// let compressed = ctx.compress_bools(&mask[..]);
+    /// assert!(compressed.size() < 100_000 / 8);
+    /// assert_eq!(mask, decompress_bools(&compressed).unwrap());
+    /// ```
+    pub fn compress_bools(&self, bits: &[bool]) -> Buffer<u8> {
+        let word = mem::size_of::<u64>();
+        let mut packed = vec![0u8; word + bits.chunks(8).len()];
+        packed[..word].copy_from_slice(&(bits.len() as u64).to_le_bytes());
+        for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+            packed[word + i / 8] |= 1 << (i % 8);
+        }
+        self.compress(&packed[..])
+    }
+
+    /// Split an array into `parts` contiguous ranges, and compress each one
+    /// separately.
+    ///
+    /// The ranges' lengths differ by at most one element.  Each part can be
+    /// decompressed independently, or all of them together with
+    /// [`decompress_concat`].  If `src` has fewer than `parts` elements, some
+    /// parts will be empty.
+    ///
+    /// The parts' boundaries are arbitrary.  To keep meaningful boundaries,
+    /// like one row per sensor, in a single buffer, use
+    /// [`compress_ragged`](Context::compress_ragged) instead.
+    ///
+    /// The parts' total sizes are cheap to compute without decompressing
+    /// anything, by summing each part's [`validate`](Buffer::validate) or
+    /// [`size`](Buffer::size).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parts` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..1000).collect();
+    /// let parts = Context::new().compress_into_parts(&data[..], 3);
+    /// assert_eq!(3, parts.len());
+    /// let uncompressed_size: usize = parts.iter().map(|part| part.validate().unwrap()).sum();
+    /// assert_eq!(4000, uncompressed_size);
+    /// let refs = parts.iter().collect::<Vec<_>>();
+    /// assert_eq!(data, decompress_concat(&refs[..]).unwrap());
+    /// ```
+    pub fn compress_into_parts<T>(&self, src: &[T], parts: usize) -> Vec<Buffer<T>> {
+        assert!(parts > 0, "Can't compress into zero parts");
+        let (len, extra) = (src.len() / parts, src.len() % parts);
+        let mut start = 0;
+        (0..parts)
+            .map(|i| {
+                let end = start + len + usize::from(i < extra);
+                let buf = self.compress(&src[start..end]);
+                start = end;
+                buf
+            })
+            .collect()
+    }
+
+    /// Compress the items of an iterator.
+    ///
+    /// The items are first collected into a temporary array, sized according
+    /// to the iterator's size hint, which is freed after compression.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let compressed = Context::new().compress_iter((0..1000u32).map(|i| i * i));
+    /// let decompressed = decompress(&compressed).unwrap();
+    /// assert_eq!(998_001, decompressed[999]);
+    /// ```
+    pub fn compress_iter<T, I>(&self, iter: I) -> Buffer<T>
+    where
+        T: Copy,
+        I: IntoIterator<Item = T>,
+    {
+        let src: Vec<T> = iter.into_iter().collect();
+        self.compress(&src[..])
+    }
+
+    /// Compress an array, and return the raw compressed bytes along with the
+    /// number of elements.
+    ///
+    /// This suits containers that do their own framing and record the element
+    /// count separately.  The bytes are the same as
+    /// [`compress`](Context::compress) would produce.  Decompress them with
+    /// [`decompress_bytes`], or with [`decompress_bytes_unchecked`] using the
+    /// element count.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let (bytes, nelem) = Context::new().compress_raw(&[1u32, 2, 3][..]);
+    /// assert_eq!(3, nelem);
+    /// let decompressed = unsafe { decompress_bytes_unchecked::<u32>(&bytes[..], nelem) };
+    /// assert_eq!(vec![1, 2, 3], decompressed.unwrap());
+    /// ```
+    pub fn compress_raw<T>(&self, src: &[T]) -> (Vec<u8>, usize) {
+        (self.compress(src).into(), src.len())
+    }
+
+    /// Compress an array into an existing `Buffer`, reusing its allocation.
+    ///
+    /// Any previous contents of `buf` are discarded.  Its capacity is kept, so
+    /// this is useful for pools of `Buffer`s.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new();
+    /// let mut buf = ctx.compress(&[0u32; 1024][..]);
+    /// ctx.compress_reuse(&[1u32, 2, 3][..], &mut buf);
+    /// assert_eq!(vec![1, 2, 3], decompress(&buf).unwrap());
+    /// ```
+    pub fn compress_reuse<T>(&self, src: &[T], buf: &mut Buffer<T>) {
+        self.compress_vec(src, &mut buf.data);
+        buf.nelem = src.len();
+    }
+
+    /// Compress an array at the lowest compression level whose output fits in
+    /// `max_bytes`.
+    ///
+    /// Levels are tried in increasing order, starting with `Clevel::None`,
+    /// and the `Context`'s own level is ignored.  Lower levels are faster to
+    /// compress, so this finds the fastest setting that meets the budget.
+    /// Returns `None` if even `Clevel::L9` doesn't fit.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..1000).collect();
+    /// let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    /// let compressed = ctx.compress_to_budget(&data[..], 2000).unwrap();
+    /// assert!(compressed.size() <= 2000);
+    /// assert_eq!(data, decompress(&compressed).unwrap());
+    /// assert!(ctx.compress_to_budget(&data[..], 16).is_none());
+    /// ```
+    pub fn compress_to_budget<T>(&self, src: &[T], max_bytes: usize) -> Option<Buffer<T>> {
+        const LEVELS: [Clevel; 10] = [
+            Clevel::None,
+            Clevel::L1,
+            Clevel::L2,
+            Clevel::L3,
+            Clevel::L4,
+            Clevel::L5,
+            Clevel::L6,
+            Clevel::L7,
+            Clevel::L8,
+            Clevel::L9,
+        ];
+        let mut dest: Vec<u8> = Vec::new();
+        for clevel in LEVELS.iter() {
+            self.clevel(*clevel).compress_vec(src, &mut dest);
+            if dest.len() <= max_bytes {
+                if self.shrink {
+                    dest.shrink_to_fit();
+                }
+                return Some(Buffer::from_vec(dest, src.len()));
+            }
+        }
+        None
+    }
+
+    /// Compress an array, unless that wouldn't save any space.
+    ///
+    /// Incompressible data gets a little bigger when compressed, because of
+    /// Blosc's header.  In that case, this returns a copy of the original
+    /// array instead.  Either way, [`decompress_stored`] can get it back.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new();
+    /// let stored = ctx.compress_or_store(&[0u32; 1000][..]);
+    /// assert!(matches!(stored, Stored::Compressed(_)));
+    ///
+    /// let stored = ctx.compress_or_store(&[1u8, 2, 3][..]);
+    /// assert!(matches!(stored, Stored::Raw(_)));
+    /// assert_eq!(vec![1, 2, 3], decompress_stored(&stored).unwrap());
+    /// ```
+    pub fn compress_or_store<T: Copy>(&self, src: &[T]) -> Stored<T> {
+        let buf = self.compress(src);
+        if buf.size() < mem::size_of_val(src) {
+            Stored::Compressed(buf)
+        } else {
+            Stored::Raw(src.to_vec())
+        }
+    }
+
+    /// Compress a string's UTF-8 bytes.
+    ///
+    /// Decompress it again with [`decompress_string`].
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let text = "It was the best of times, it was the worst of times";
+    /// let compressed = Context::new().compress_str(text);
+    /// assert_eq!(text, decompress_string(&compressed).unwrap());
+    /// ```
+    pub fn compress_str(&self, s: &str) -> Buffer<u8> {
+        self.compress(s.as_bytes())
+    }
+
+    /// Compress an array into memory owned by the caller, and return the
+    /// compressed size.
+    ///
+    /// `dest` must have room for the worst case, which is
+    /// [`max_compressed_len`](Context::max_compressed_len) of `src`'s size.
+    /// Otherwise [`BloscError::DestTooSmall`] is returned, even if the output
+    /// would have fit.  Only the returned number of bytes are meaningful.
+    ///
+    /// Nothing is allocated, unless [`big_endian`](Context::big_endian) or
+    /// [`little_endian`](Context::little_endian) requires swapping bytes.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// # use std::mem;
+    /// let data: Vec<u32> = (0..1000).collect();
+    /// let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    /// let mut slab = vec![0u8; ctx.max_compressed_len(mem::size_of_val(&data[..]))];
+    /// let len = ctx.compress_into_slice(&data[..], &mut slab[..]).unwrap();
+    /// let decompressed = unsafe { decompress_bytes::<u32>(&slab[..len]) };
+    /// assert_eq!(data, decompressed.unwrap());
+    /// ```
+    pub fn compress_into_slice<T>(&self, src: &[T], dest: &mut [u8]) -> Result<usize> {
+        max_frame_len(mem::size_of_val(src))?;
+        if dest.len() < self.max_compressed_len(mem::size_of_val(src)) {
+            return Err(BloscError::DestTooSmall);
+        }
+        let trailer_len = self.checksum.map_or(0, Checksum::len);
+        let frame_size = dest.len() - trailer_len;
+        let rsize = unsafe { self.compress_to_ptr(src, dest.as_mut_ptr(), frame_size) };
+        match self.checksum {
+            Some(checksum) => {
+                let trailer = checksum.compute(&dest[..rsize]);
+                dest[rsize..rsize + trailer_len].copy_from_slice(&trailer[..]);
+                Ok(rsize + trailer_len)
+            }
+            None => Ok(rsize),
+        }
+    }
+
+    /// Compress preserialized records whose typesize is only known at
+    /// runtime.
+    ///
+    /// This is like setting [`typesize`](Context::typesize) and compressing
+    /// `src`, but it insists that `src` holds a whole number of records.
+    /// Otherwise the shuffle filter would leave a partial record at the end
+    /// unshuffled, so [`BloscError::TypesizeMismatch`] is returned instead.
+    /// That includes a `typesize` of 0.
+    ///
+    /// The typesize is recorded in the buffer's header, where
+    /// [`decompress_bytes_with_len`] can find it, unless it's larger than 255.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let records = [0u8; 24];
+    /// let compressed = Context::new().compress_with_schema(&records[..], 6).unwrap();
+    /// let (data, typesize) = unsafe { decompress_bytes_with_len::<u8>(compressed.as_ref()) }.unwrap();
+    /// assert_eq!((&records[..], 6), (&data[..], typesize));
+    ///
+    /// let r = Context::new().compress_with_schema(&records[..], 5);
+    /// assert!(matches!(r, Err(BloscError::TypesizeMismatch)));
+    /// ```
+    pub fn compress_with_schema(&self, src: &[u8], typesize: usize) -> Result<Buffer<u8>> {
+        if src.len().checked_rem(typesize) != Some(0) {
+            return Err(BloscError::TypesizeMismatch);
+        }
+        Ok(self.typesize(Some(typesize)).compress(src))
+    }
+
+    /// Compress an array, and also report how well it compressed.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data = vec![0u64; 1000];
+    /// let (compressed, stats) = Context::new().compress_with_stats(&data[..]);
+    /// assert_eq!(8000, stats.uncompressed_bytes);
+    /// assert_eq!(compressed.size(), stats.compressed_bytes);
+    /// assert!(stats.ratio > 1.0);
+    /// assert!(!stats.memcpyed);
+    /// ```
+    pub fn compress_with_stats<T>(&self, src: &[T]) -> (Buffer<T>, CompressStats) {
+        let buf = self.compress(src);
+        let uncompressed_bytes = mem::size_of_val(src);
+        let compressed_bytes = buf.size();
+        let stats = CompressStats {
+            uncompressed_bytes,
+            compressed_bytes,
+            ratio: uncompressed_bytes as f64 / compressed_bytes as f64,
+            memcpyed: matches!(buf.flags(), Ok(flags) if flags.memcpyed()),
+        };
+        (buf, stats)
+    }
+
+    /// Compress an array, and also measure how long it took.
+    ///
+    /// Requires the `metrics` feature.  Without it, nothing is timed.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data = vec![0u64; 1000];
+    /// let (compressed, timing) = Context::new().compress_instrumented(&data[..]);
+    /// assert_eq!(8000, timing.bytes_in);
+    /// assert_eq!(compressed.size(), timing.bytes_out);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn compress_instrumented<T>(&self, src: &[T]) -> (Buffer<T>, Timing) {
+        let start = std::time::Instant::now();
+        let buf = self.compress(src);
+        let timing = Timing {
+            elapsed: start.elapsed(),
+            bytes_in: mem::size_of_val(src),
+            bytes_out: buf.size(),
+        };
+        (buf, timing)
+    }
+
+    /// Compress `src` into `dest`, replacing its contents.
+    fn compress_vec<T>(&self, src: &[T], dest: &mut Vec<u8>) {
+        let src_size = mem::size_of_val(src);
+        let full_size = max_frame_len(src_size).unwrap_or_else(|_| {
+            panic!(
+                "Can't compress {} bytes; C-Blosc's limit is {} bytes",
+                src_size, BLOSC_MAX_BUFFERSIZE
+            )
+        });
+        let mut dest_size = match self.dest_reserve_hint {
+            Some(hint) => hint.max(BLOSC_MAX_OVERHEAD as usize).min(full_size),
+            None => full_size,
+        };
+        dest.clear();
+        dest.reserve(dest_size);
+        let mut rsize = unsafe { self.compress_to_ptr(src, dest.as_mut_ptr(), dest_size) };
+        if rsize == 0 && dest_size < full_size {
+            // The output didn't fit in the reserve hint
+            dest_size = full_size;
+            dest.reserve(dest_size);
+            rsize = unsafe { self.compress_to_ptr(src, dest.as_mut_ptr(), dest_size) };
+        }
+        unsafe {
+            dest.set_len(rsize);
+        }
+        if let Some(checksum) = self.checksum {
+            let trailer = checksum.compute(&dest[..]);
+            dest.extend_from_slice(&trailer[..]);
+        }
+    }
+
+    /// Compress `src` into the `dest_size` bytes at `dest`, without any
+    /// checksum, and return the compressed size.  That's 0 if the output
+    /// didn't fit.
+    ///
+    /// # Safety
+    ///
+    /// `dest` must be valid for writes of `dest_size` bytes.
+    unsafe fn compress_to_ptr<T>(&self, src: &[T], dest: *mut u8, dest_size: usize) -> usize {
+        // C-Blosc can't handle a typesize of 0, as for zero-sized types
+        let typesize = self.typesize.unwrap_or(mem::size_of::<T>()).max(1);
+        let src_size = mem::size_of_val(src);
+        let swapped: Vec<u8>;
+        let mut src_ptr = src.as_ptr() as *const c_void;
+        if matches!(self.byte_order, Some(order) if !order.is_native()) {
+            let bytes = slice::from_raw_parts(src.as_ptr() as *const u8, src_size);
+            let mut bytes = bytes.to_vec();
+            // C-Blosc records oversized types as a typesize of 1
+            if typesize <= BLOSC_MAX_TYPESIZE as usize {
+                swap_elements(&mut bytes[..], typesize);
+            }
+            swapped = bytes;
+            src_ptr = swapped.as_ptr() as *const c_void;
+        }
+        let shuffle_mode = if src_size < self.auto_small {
+            ShuffleMode::None
+        } else {
+            self.shuffle_mode.resolve(typesize)
+        };
+        let compress = || {
+            blosc_compress_ctx(
+                self.clevel as c_int,
+                shuffle_mode as c_int,
+                typesize,
+                src_size,
+                src_ptr,
+                dest as *mut c_void,
+                dest_size,
+                self.compressor.into(),
+                self.effective_blocksize(src_size, typesize),
+                1,
+            )
+        };
+        let rsize = if let Some(split_mode) = self.split_mode {
+            let global = global_split_mode()
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            blosc_set_splitmode(split_mode as c_int);
+            let rsize = compress();
+            blosc_set_splitmode(*global);
+            rsize
+        } else {
+            let _global = global_split_mode()
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            compress()
+        };
+        // Blosc's docs claim that blosc_compress_ctx should never return an
+        // error
+        // LCOV_EXCL_START
+        assert!(
+            rsize >= 0,
+            "C-Blosc internal error with Context={:?}, typesize={:?} nbytes={:?} and destsize={:?}",
+            self,
+            typesize,
+            src_size,
+            dest_size
+        );
+        // LCOV_EXCL_STOP
+        rsize as usize
+    }
+
+    /// Compress an array of variable-length rows.
+    ///
+    /// The rows are concatenated and compressed as a single array, and their
+    /// lengths are recorded so that [`decompress_ragged`] can split them apart
+    /// again.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let rows: Vec<Vec<f32>> = vec![vec![1.0, 2.0], vec![], vec![3.0]];
+    /// let slices = rows.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    /// let compressed = Context::new().compress_ragged(&slices[..]);
+    /// assert_eq!(rows, decompress_ragged(&compressed).unwrap());
+    /// ```
+    pub fn compress_ragged<T: Copy>(&self, rows: &[&[T]]) -> RaggedBuffer<T> {
+        let flat = rows.concat();
+        let compressed = self.compress(&flat[..]);
+        let prefix_len = (rows.len() + 1) * mem::size_of::<u64>();
+        let mut data = Vec::with_capacity(prefix_len + compressed.size());
+        data.extend_from_slice(&(rows.len() as u64).to_le_bytes());
+        for row in rows {
+            data.extend_from_slice(&(row.len() as u64).to_le_bytes());
+        }
+        data.extend_from_slice(compressed.as_ref());
+        RaggedBuffer {
+            data,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Compress an array of structs one field at a time.
+    ///
+    /// The fields are transposed into a separate column each, as described by
+    /// [`Columnar`], and each column is compressed with its field's size as
+    /// the typesize.  That overrides the `Context`'s own typesize.
+    /// [`decompress_soa`] transposes them back.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// #[derive(Clone, Copy, Debug, PartialEq)]
+    /// #[repr(C)]
+    /// struct Sample {
+    ///     value: f64,
+    ///     channel: u8,
+    /// }
+    ///
+    /// unsafe impl Columnar for Sample {
+    ///     const FIELDS: &'static [(usize, usize)] = &[(0, 8), (8, 1)];
+    /// }
+    ///
+    /// let samples: Vec<Sample> = (0..1000)
+    ///     .map(|i| Sample { value: f64::from(i) / 8.0, channel: (i % 4) as u8 })
+    ///     .collect();
+    /// let compressed = Context::new().compress_soa(&samples[..]);
+    /// assert_eq!(samples, decompress_soa(&compressed).unwrap());
+    /// ```
+    pub fn compress_soa<T: Columnar>(&self, src: &[T]) -> SoaBuffer<T> {
+        let elem_size = mem::size_of::<T>();
+        // Only the fields are read, never the padding
+        let base = src.as_ptr() as *const u8;
+        let mut columns = ColumnSet::new();
+        let mut column = Vec::new();
+        for &(offset, size) in T::FIELDS {
+            assert!(offset + size <= elem_size, "Field is outside of the struct");
+            column.clear();
+            column.reserve(src.len() * size);
+            for i in 0..src.len() {
+                let field =
+                    unsafe { slice::from_raw_parts(base.add(i * elem_size + offset), size) };
+                column.extend_from_slice(field);
+            }
+            // C-Blosc can't handle a typesize of 0
+            columns.push(&self.typesize(Some(size.max(1))), &column[..]);
+        }
+        let mut data = (src.len() as u64).to_le_bytes().to_vec();
+        data.extend_from_slice(&columns.finish()[..]);
+        SoaBuffer {
+            data,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Compute a stable 64-bit fingerprint of the settings that affect the
+    /// compressed output, for use in cache keys.
+    ///
+    /// The fingerprint is the 64-bit FNV-1a hash of these settings, each
+    /// encoded as a little-endian `u64`, in this order:
+    ///
+    /// 1. The compression level, from 0 to 9.
+    /// 2. The compressor's [`to_id`](Compressor::to_id) code.
+    /// 3. The shuffle mode: 0 for none, 1 for byte, 2 for bit, or 3 for auto.
+    /// 4. The typesize, or 0 if it's detected when compressing.
+    /// 5. The blocksize, or 0 if it's automatic.
+    /// 6. The [`target_blocks`](Context::target_blocks), or 0 if unset.
+    /// 7. The split mode: 0 for the global default, 1 for always, 2 for never,
+    ///    3 for auto, or 4 for forward compatible.
+    /// 8. The byte order: 0 for native, 1 for big endian, or 2 for little
+    ///    endian.
+    /// 9. The checksum: 0 for none, or 1 for CRC-32C.
+    /// 10. The [`auto_small`](Context::auto_small) threshold.
+    ///
+    /// [`shrink`](Context::shrink) and
+    /// [`dest_reserve_hint`](Context::dest_reserve_hint) only affect memory
+    /// use, so they're left out.  This encoding won't change in future
+    /// versions of this crate, except to append new settings when they're
+    /// not at their defaults.  So the fingerprint may be used as an on-disk
+    /// cache key.  It doesn't account for the version of C-Blosc, which may
+    /// change the output too.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new().clevel(Clevel::L9);
+    /// assert_eq!(ctx.fingerprint(), ctx.shrink(false).fingerprint());
+    /// assert_ne!(ctx.fingerprint(), Context::new().fingerprint());
+    /// ```
+    pub const fn fingerprint(&self) -> u64 {
+        const fn fnv1a(mut hash: u64, word: u64) -> u64 {
+            let bytes = word.to_le_bytes();
+            let mut i = 0;
+            while i < bytes.len() {
+                hash ^= bytes[i] as u64;
+                hash = hash.wrapping_mul(0x100_0000_01b3);
+                i += 1;
+            }
+            hash
+        }
+        const fn code(value: Option<usize>) -> u64 {
+            match value {
+                Some(value) => value as u64,
+                None => 0,
+            }
+        }
+        let shuffle = match self.shuffle_mode {
+            ShuffleMode::None => 0,
+            ShuffleMode::Byte => 1,
+            ShuffleMode::Bit => 2,
+            ShuffleMode::Auto => 3,
+        };
+        let split = match self.split_mode {
+            None => 0,
+            Some(SplitMode::Always) => 1,
+            Some(SplitMode::Never) => 2,
+            Some(SplitMode::Auto) => 3,
+            Some(SplitMode::ForwardCompat) => 4,
+        };
+        let byte_order = match self.byte_order {
+            None => 0,
+            Some(ByteOrder::Big) => 1,
+            Some(ByteOrder::Little) => 2,
+        };
+        let checksum = match self.checksum {
+            None => 0,
+            Some(Checksum::Crc32c) => 1,
+        };
+        let words = [
+            self.clevel as u64,
+            self.compressor.to_id() as u64,
+            shuffle,
+            code(self.typesize),
+            self.blocksize as u64,
+            code(self.target_blocks),
+            split,
+            byte_order,
+            checksum,
+            self.auto_small as u64,
+        ];
+        let mut hash = 0xcbf2_9ce4_8422_2325;
+        let mut i = 0;
+        while i < words.len() {
+            hash = fnv1a(hash, words[i]);
+            i += 1;
+        }
+        hash
+    }
+
+    /// Build a default compression context for arrays of `T`.
+    ///
+    /// This is the same as [`new`](Context::new), but the typesize is set
+    /// explicitly to the size of `T`, rather than being detected when
+    /// compressing.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// #[derive(Clone, Copy)]
+    /// struct Point {
+    ///     x: f32,
+    ///     y: f32,
+    /// }
+    /// let ctx = Context::for_elements::<Point>();
+    /// assert_eq!(ctx, Context::new().typesize(Some(8)));
+    /// ```
+    pub const fn for_elements<T>() -> Self {
+        Self::new().typesize(Some(mem::size_of::<T>()))
+    }
+
+    /// Choose how much memory [`compress`](Context::compress) initially
+    /// reserves for its output, in bytes.
+    ///
+    /// By default, room for the worst case is reserved: the input's size plus
+    /// `BLOSC_MAX_OVERHEAD`.  For highly compressible data, a smaller hint
+    /// reduces peak memory use.  If the output doesn't fit, compression is
+    /// retried with the full reservation, so a hint that's too small costs
+    /// time but never fails.  Hints smaller than `BLOSC_MAX_OVERHEAD` are
+    /// rounded up.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..100_000).collect();
+    /// let ctx = Context::new()
+    ///     .shuffle(ShuffleMode::Byte)
+    ///     .dest_reserve_hint(Some(8192));
+    /// let compressed = ctx.compress(&data[..]);
+    /// assert_eq!(data, decompress(&compressed).unwrap());
+    /// ```
+    pub const fn dest_reserve_hint(mut self, bytes: Option<usize>) -> Self {
+        self.dest_reserve_hint = bytes;
+        self
+    }
+
+    /// Build a `Context` with the same settings that were used to compress
+    /// `buf`.
+    ///
+    /// The compressor, shuffle mode, typesize, and blocksize are read from the
+    /// buffer's header.  Some settings can't be recovered that way:
+    ///
+    /// * The compression level isn't recorded, so it will be `Clevel::L2`.
+    /// * `LZ4HC` can't be distinguished from `LZ4`, so it will be `LZ4`.
+    /// * An automatic blocksize will become a fixed one.
+    ///
+    /// A checksum trailer, if present, is also detected.
+    ///
+    /// Returns an error if the buffer's compressor isn't supported by this
+    /// build of C-Blosc.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new()
+    ///     .compressor(Compressor::Zstd).unwrap()
+    ///     .shuffle(ShuffleMode::Bit);
+    /// let compressed = ctx.compress(&[0u64; 1000][..]);
+    /// let ctx2 = Context::from_buffer(&compressed).unwrap();
+    /// assert_eq!(ctx2, ctx.blocksize(Some(8000)).typesize(Some(8)));
+    /// ```
+    pub fn from_buffer<T>(buf: &Buffer<T>) -> Result<Context> {
+        let typesize = cbuffer_metainfo(&buf.data[..])?.0;
+        let compressor = buf.effective_compressor()?;
+        let mut ctx = Context::new()
+            .blocksize(Some(buf.blocksize()))
+            .compressor(compressor)?
+            .shuffle(ShuffleMode::from_flags(buf.flags()?))
+            .typesize(Some(typesize));
+        ctx.checksum = Checksum::from_len(frame_and_trailer(&buf.data[..]).1.len());
+        Ok(ctx)
+    }
+
+    /// Build a default compression context.
+    ///
+    /// The compressor is always `BloscLZ`, so that `new` can be a `const fn`.
+    /// Use [`Context::default`] instead to get the compressor chosen by
+    /// [`set_default_compressor`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use blosc::*;
+    /// # #[allow(unused)]
+    /// let ctx = Context::new()
+    ///     .blocksize(Some(262144))
+    ///     .compressor(Compressor::Zstd).unwrap()
+    ///     .clevel(Clevel::L9)
+    ///     .shuffle(ShuffleMode::Bit);
+    /// ```
+    pub const fn new() -> Self {
+        Context {
+            auto_small: 0,                   // Always shuffle
+            blocksize: 0,                    // Automatic blocksize
+            byte_order: None,                // Leave elements in host order
+            checksum: None,                  // Plain Blosc frames by default
+            clevel: Clevel::L2,              // Level 2 selects blocksize to equal L1 cache
+            compressor: Compressor::BloscLZ, // Default algorithm
+            dest_reserve_hint: None,         // Reserve for the worst case
+            shrink: true,                    // Don't waste memory
+            shuffle_mode: ShuffleMode::None, // Don't shuffle by default
+            split_mode: None,                // Use C-Blosc's global setting
+            target_blocks: None,             // Use the blocksize instead
+            typesize: None,                  // autodetect by default
+        }
+    }
+
+    /// Disable every filter, so the data reaches the compressor unchanged.
+    ///
+    /// This is useful for data that's already been shuffled or transposed.
+    /// It resets the shuffle mode to [`ShuffleMode::None`], clears any
+    /// [`auto_small`](Context::auto_small) threshold, and leaves elements in
+    /// host byte order.  C-Blosc 1.x has no other filters, like delta or
+    /// precision truncation, so nothing else will touch the data.  A filter
+    /// that's selected after calling `no_filter` still applies.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::ZSTD_BITSHUFFLE_L9.no_filter();
+    /// let compressed = ctx.compress(&[1u32, 2, 3][..]);
+    /// let flags = compressed.flags().unwrap();
+    /// assert!(!flags.byte_shuffle() && !flags.bit_shuffle());
+    /// ```
+    pub const fn no_filter(mut self) -> Self {
+        self.auto_small = 0;
+        self.byte_order = None;
+        self.shuffle_mode = ShuffleMode::None;
+        self
+    }
+
+    /// Check these settings for problems, regardless of the element type.
+    ///
+    /// This is like [`validate`](Context::validate), but it only knows the
+    /// typesize if [`typesize`](Context::typesize) was set.  It also gives
+    /// advice of [`Severity::Info`], like suggesting a shuffle filter for
+    /// numeric-looking typesizes.  Use [`Warning::severity`] to tell them
+    /// apart.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new().typesize(Some(8));
+    /// let lints = ctx.lint();
+    /// assert_eq!(vec![Warning::NoShuffle(8)], lints);
+    /// assert_eq!(Severity::Info, lints[0].severity());
+    /// assert!(ctx.shuffle(ShuffleMode::Byte).lint().is_empty());
+    /// ```
+    pub fn lint(&self) -> Vec<Warning> {
+        let mut warnings = self.warnings(self.typesize, None);
+        if let Some(typesize) = self.typesize {
+            if self.shuffle_mode == ShuffleMode::None
+                && typesize > 1
+                && typesize <= max_typesize()
+                && typesize.is_power_of_two()
+            {
+                warnings.push(Warning::NoShuffle(typesize));
+            }
+        }
+        warnings
+    }
+
+    /// Store elements in little-endian order, regardless of the host's byte
+    /// order.
+    ///
+    /// This is the same as [`big_endian`](Context::big_endian), but it's a
+    /// no-op on little-endian hosts.  That makes it the cheapest canonical
+    /// order for data that will be exchanged between architectures.
+    pub const fn little_endian(mut self) -> Self {
+        self.byte_order = Some(ByteOrder::Little);
+        self
+    }
+
+    /// Return the largest possible compressed size of `src_bytes` bytes of
+    /// input, including any checksum.
+    pub const fn max_compressed_len(&self, src_bytes: usize) -> usize {
+        let trailer_len = match self.checksum {
+            Some(checksum) => checksum.len(),
+            None => 0,
+        };
+        src_bytes
+            .saturating_add(BLOSC_MAX_OVERHEAD as usize)
+            .saturating_add(trailer_len)
+    }
+
+    /// Report how well an array would compress, without keeping the result.
+    ///
+    /// The ratio is computed the same way as [`CompressStats::ratio`], but the
+    /// compressed output goes into a per-thread scratch buffer that's reused
+    /// across calls.  So measuring many samples doesn't allocate for each
+    /// one.  The scratch buffer stays as large as the largest sample measured
+    /// on its thread.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new();
+    /// assert!(ctx.measure_ratio(&[0u64; 1000][..]) > 1.0);
+    /// ```
+    pub fn measure_ratio<T>(&self, src: &[T]) -> f64 {
+        MEASURE_SCRATCH.with(|scratch| {
+            let mut scratch = scratch.borrow_mut();
+            self.compress_vec(src, &mut scratch);
+            mem::size_of_val(src) as f64 / scratch.len() as f64
+        })
+    }
+
+    /// Select whether [`compress`](Context::compress) should release the
+    /// unused capacity of its output.
+    ///
+    /// Compression needs room for the worst case, which is slightly larger
+    /// than the input.  By default that excess is released afterwards, which
+    /// costs a reallocation and copy.  Disabling this saves time for
+    /// short-lived buffers, at the cost of memory.
+    pub const fn shrink(mut self, enabled: bool) -> Self {
+        self.shrink = enabled;
+        self
+    }
+
+    /// Select which Shuffle filter to apply before compression.
+    pub const fn shuffle(mut self, shuffle_mode: ShuffleMode) -> Self {
+        self.shuffle_mode = shuffle_mode;
+        self
+    }
+
+    /// Select whether C-Blosc should split blocks before compressing them.
+    ///
+    /// If unset, the global split mode will be used.  That's
+    /// [`SplitMode::ForwardCompat`] unless changed by
+    /// [`set_global_splitmode`].
+    ///
+    /// # Thread safety
+    ///
+    /// C-Blosc 1.x only supports the split mode as a process-wide setting,
+    /// even for individual compression contexts.  So `compress` temporarily
+    /// changes that setting, while holding a process-wide lock that keeps
+    /// other compressions from this crate waiting.  Contexts with a split mode
+    /// therefore can't compress in parallel with any other `Context`.  C code
+    /// in the same process that uses C-Blosc directly isn't synchronized.
+    pub const fn split_mode(mut self, split_mode: SplitMode) -> Self {
+        self.split_mode = Some(split_mode);
+        self
+    }
+
+    /// Choose a blocksize that will divide the input into about `n` blocks.
+    ///
+    /// Blocks can be decompressed independently, so this suits decompressing
+    /// with `n` threads.  The blocksize is computed when compressing, from the
+    /// input's size.  It's rounded up to a multiple of the typesize, and
+    /// clamped to C-Blosc's limits of 128 bytes and about 715 MB per block.  So
+    /// the last block may be short, and small or huge inputs may get a
+    /// different number of blocks.
+    ///
+    /// When blocks will be split, C-Blosc enlarges them further; see
+    /// [`SplitMode`].  For the most accurate block count, also choose
+    /// [`SplitMode::Never`], or a compressor like `Zstd` that isn't split by
+    /// default.
+    ///
+    /// This takes precedence over [`blocksize`](Context::blocksize).  `None`
+    /// restores it.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..100_000).collect();
+    /// let ctx = Context::new()
+    ///     .split_mode(SplitMode::Never)
+    ///     .target_blocks(Some(4));
+    /// let compressed = ctx.compress(&data[..]);
+    /// assert_eq!(100_000, compressed.blocksize());
+    /// ```
+    pub const fn target_blocks(mut self, n: Option<usize>) -> Self {
+        self.target_blocks = n;
+        self
+    }
+
+    /// The blocksize to request from C-Blosc for `src_size` bytes of input,
+    /// or 0 for automatic.
+    fn effective_blocksize(&self, src_size: usize, typesize: usize) -> usize {
+        match self.target_blocks {
+            Some(n) if src_size > 0 => {
+                let n = n.max(1);
+                let per_block = src_size / n + usize::from(src_size.checked_rem(n) != Some(0));
+                let typesize = typesize.max(1);
+                let elems =
+                    per_block / typesize + usize::from(per_block.checked_rem(typesize) != Some(0));
+                elems
+                    .saturating_mul(typesize)
+                    .clamp(BLOSC_MIN_BLOCKSIZE, BLOSC_MAX_BLOCKSIZE)
+            }
+            _ => self.blocksize,
+        }
+    }
+
+    /// Manually set the size in bytes to assume for each uncompressed array
+    /// element.
+    ///
+    /// The `typesize` is used for Blosc's shuffle operation.  When compressing
+    /// arrays, the `typesize` should be the size of each array element.  If
+    /// `None` or unspecified, it will be autodetected.  However, manually
+    /// setting `typesize` can be useful when compressing preserialized buffers
+    /// or single structures that contain arrays.
+    ///
+    /// Typesizes larger than [`max_typesize`], whether set here or
+    /// autodetected, silently disable the shuffle filter.
+    /// [`validate`](Context::validate) reports that.
     ///
     /// # Examples
     ///
-    /// Set the `typesize` when compressing an array-containing struct
+    /// Set the `typesize` when compressing an array-containing struct
+    ///
+    /// ```
+    /// # use blosc::*;
+    /// # use std::mem;
+    /// #[derive(Default)]
+    /// struct Foo {
+    ///     x: usize,
+    ///     y: [u32; 32]
+    /// }
+    /// let foo = [Foo::default()];
+    /// let ctx = Context::new().typesize(Some(mem::size_of_val(&foo[0].y[0])));
+    /// ctx.compress(&foo[..]);
+    /// ```
+    ///
+    /// Set the `typesize` when compressing preserialized data.
+    ///
+    /// ```
+    /// # extern crate bincode;
+    /// # extern crate blosc;
+    /// # extern crate serde;
+    /// # use blosc::*;
+    /// # use std::mem;
+    /// let raw: Vec<i16> = vec![0, 1, 2, 3, 4, 5];
+    /// let serialized = bincode::serialize(&raw).unwrap();
+    /// let ctx = Context::new().typesize(Some(mem::size_of::<i16>()));
+    /// ctx.compress(&serialized[..]);
+    /// ```
+    pub const fn typesize(mut self, typesize: Option<usize>) -> Self {
+        self.typesize = typesize;
+        self
+    }
+
+    /// Set the typesize to the size of `sample`'s elements.
+    ///
+    /// This is the same as [`for_elements`](Context::for_elements), but the
+    /// element type is inferred from a value.  Only the type of `sample` is
+    /// used, so it may be empty.  That's useful when compressing the
+    /// serialized bytes of a typed array.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let samples = vec![0.5f64, 1.5, 2.5];
+    /// let bytes: Vec<u8> = samples.iter().flat_map(|x| x.to_le_bytes()).collect();
+    /// let ctx = Context::new().typesize_of_slice(&samples[..]);
+    /// assert_eq!(Context::new().typesize(Some(8)), ctx);
+    /// ctx.compress(&bytes[..]);
+    /// ```
+    pub const fn typesize_of_slice<T>(self, _sample: &[T]) -> Self {
+        self.typesize(Some(mem::size_of::<T>()))
+    }
+
+    /// Check these settings for problems when compressing `src`.
+    ///
+    /// This reports everything that [`validate`](Context::validate) does, and
+    /// also inspects a sample of `src` for data that was already shuffled
+    /// upstream, reported as [`Warning::LikelyPreshuffled`].  Shuffling such
+    /// data again usually worsens compression.
+    ///
+    /// Pre-shuffled data can't be detected reliably, so this is a best-effort
+    /// heuristic.  In ordinary numeric arrays, bytes one element apart are
+    /// equal much more often than adjacent bytes, because they're the same
+    /// byte of neighboring elements.  After shuffling, those bytes are
+    /// adjacent.  So data whose adjacent bytes are equal about as often is
+    /// flagged.  Random and constant data are never flagged, but mostly-zero
+    /// data may be.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let data: Vec<u32> = (0..10_000).collect();
+    /// let ctx = Context::new().typesize(Some(4)).shuffle(ShuffleMode::Byte);
+    /// let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes()).collect();
+    /// assert!(ctx.lint_data(&bytes[..]).is_empty());
+    ///
+    /// // Shuffle it by hand, as an upstream stage might have done
+    /// let planes: Vec<u8> = (0..4)
+    ///     .flat_map(|j| bytes.iter().skip(j).step_by(4).copied())
+    ///     .collect();
+    /// assert_eq!(vec![Warning::LikelyPreshuffled(4)], ctx.lint_data(&planes[..]));
+    /// ```
+    pub fn lint_data<T>(&self, src: &[T]) -> Vec<Warning> {
+        let elem_size = mem::size_of::<T>();
+        let typesize = self.typesize.unwrap_or(elem_size);
+        let mut warnings = self.validate::<T>();
+        let src_size = mem::size_of_val(src);
+        let shuffled = src_size >= self.auto_small
+            && self.shuffle_mode.resolve(typesize) != ShuffleMode::None
+            && typesize > 1
+            && typesize <= max_typesize();
+        if shuffled {
+            // Any data may be viewed as bytes
+            let bytes = unsafe { slice::from_raw_parts(src.as_ptr() as *const u8, src_size) };
+            if looks_shuffled(bytes, typesize) {
+                warnings.push(Warning::LikelyPreshuffled(typesize));
+            }
+        }
+        warnings
+    }
+
+    /// Check these settings for problems when compressing arrays of `T`.
+    ///
+    /// The problems found are not errors: compression will still work, but
+    /// may give poor results.  An empty `Vec` means no problems were found.
+    /// For example, a typesize that doesn't evenly divide the size of `T` will
+    /// be reported, as will a compression level that the compressor ignores.
+    ///
+    /// # Example
+    /// ```
+    /// # use blosc::*;
+    /// let ctx = Context::new().shuffle(ShuffleMode::Bit);
+    /// assert!(ctx.validate::<u64>().is_empty());
+    /// assert_eq!(vec![Warning::BitshuffleTypesize(3)], ctx.validate::<[u8; 3]>());
+    /// ```
+    pub fn validate<T>(&self) -> Vec<Warning> {
+        let elem_size = mem::size_of::<T>();
+        self.warnings(Some(self.typesize.unwrap_or(elem_size)), Some(elem_size))
+    }
+
+    /// Check these settings for problems, with the given typesize and element
+    /// size if known.
+    fn warnings(&self, typesize: Option<usize>, elem_size: Option<usize>) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        if let Some(typesize) = typesize {
+            let shuffle_mode = self.shuffle_mode.resolve(typesize);
+            if shuffle_mode != ShuffleMode::None && typesize > max_typesize() {
+                warnings.push(Warning::TypesizeTooLarge(typesize));
+            } else if shuffle_mode == ShuffleMode::Bit && !typesize.is_power_of_two() {
+                warnings.push(Warning::BitshuffleTypesize(typesize));
+            }
+            if shuffle_mode != ShuffleMode::None
+                && self.blocksize > 0
+                && self.blocksize.max(BLOSC_MIN_BLOCKSIZE) < typesize
+            {
+                warnings.push(Warning::BlocksizeTooSmall(self.blocksize, typesize));
+            }
+            // Byte arrays are usually preserialized data, whose real element
+            // size is unknown.
+            if let Some(elem_size) = elem_size {
+                if elem_size > 1 && elem_size.checked_rem(typesize) != Some(0) {
+                    warnings.push(Warning::TypesizeMismatch(typesize, elem_size));
+                }
+            }
+        }
+        if self.compressor == Compressor::Snappy && self.clevel > Clevel::None {
+            warnings.push(Warning::ClevelIgnored(self.compressor));
+        }
+        warnings
+    }
+
+    /// Append a checksum of the compressed data to every compressed buffer.
+    ///
+    /// [`decompress`] will verify the checksum, and so will
+    /// [`verify_checksum`].  The checksum is a nonstandard extension; see
+    /// [`Checksum`] for compatibility notes.
     ///
+    /// # Example
     /// ```
     /// # use blosc::*;
-    /// # use std::mem;
-    /// #[derive(Default)]
-    /// struct Foo {
-    ///     x: usize,
-    ///     y: [u32; 32]
-    /// }
-    /// let foo = [Foo::default()];
-    /// let ctx = Context::new().typesize(Some(mem::size_of_val(&foo[0].y[0])));
-    /// ctx.compress(&foo[..]);
+    /// let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    /// let compressed = ctx.compress(&[1u32, 2, 3][..]);
+    /// assert_eq!(Ok(()), verify_checksum(&compressed));
+    /// assert_eq!(vec![1, 2, 3], decompress(&compressed).unwrap());
     /// ```
+    pub const fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+
+    /// Select the `Context`'s compression algorithm, without checking whether
+    /// this build of C-Blosc supports it.
     ///
-    /// Set the `typesize` when compressing preserialized data.
+    /// Unlike [`compressor`](Context::compressor), this is a `const fn`, so it
+    /// can be used to build a `const` `Context`.
+    ///
+    /// # Panics
+    ///
+    /// Compression will panic if the compressor isn't supported.  Use
+    /// [`compressor`](Context::compressor) instead when that isn't known in
+    /// advance.
     ///
+    /// # Example
     /// ```
-    /// # extern crate bincode;
-    /// # extern crate blosc;
-    /// # extern crate serde;
     /// # use blosc::*;
-    /// # use std::mem;
-    /// let raw: Vec<i16> = vec![0, 1, 2, 3, 4, 5];
-    /// let serialized = bincode::serialize(&raw).unwrap();
-    /// let ctx = Context::new().typesize(Some(mem::size_of::<i16>()));
-    /// ctx.compress(&serialized[..]);
+    /// const CTX: Context = Context::new()
+    ///     .clevel(Clevel::L5)
+    ///     .shuffle(ShuffleMode::Byte)
+    ///     .with_compressor(Compressor::LZ4);
+    /// let compressed = CTX.compress(&[1u32, 2, 3][..]);
+    /// assert_eq!(vec![1, 2, 3], decompress(&compressed).unwrap());
     /// ```
-    pub const fn typesize(mut self, typesize: Option<usize>) -> Self {
-        self.typesize = typesize;
+    pub const fn with_compressor(mut self, compressor: Compressor) -> Self {
+        self.compressor = compressor;
         self
     }
 }
 
-impl Default for Context {
-    fn default() -> Self {
-        Self::new()
+/// The same as [`Context::new`], except that the compressor is the one chosen
+/// by [`set_default_compressor`].
+impl Default for Context {
+    fn default() -> Self {
+        let id = DEFAULT_COMPRESSOR.load(Ordering::Relaxed);
+        let compressor = Compressor::from_id(id).unwrap_or_default();
+        Self::new().with_compressor(compressor)
+    }
+}
+
+/// Decompress a `blosc::Buffer` into a newly allocated `Vec`
+///
+/// If the buffer has a checksum, it will be verified first.  The `Vec` is
+/// allocated once, with room for exactly the buffer's elements, so it's never
+/// reallocated or shrunk.
+///
+/// A typesize forced by [`Context::typesize`] needs no special handling.
+/// C-Blosc reads the typesize from the buffer's header to undo the shuffle
+/// filter, and the number of `T`s is the number that were compressed.  Use
+/// [`decompress_bytes_with_len`] to learn the recorded typesize.
+///
+/// # Safety
+///
+/// `decompress` is safe to use because the compiler will guarantee that `src`
+/// came from the output of `Context::compress`.
+///
+/// # Example
+/// The compiler won't allow decompressing into the wrong type.
+///
+/// ```compile_fail
+/// # use blosc::*;
+/// let data: Vec<u16> = vec![1, 2, 3, 65535];
+/// let ctx = Context::new();
+/// let compressed = ctx.compress(&data[..]);
+/// let decompressed: Vec<i16> = decompress(&compressed).unwrap();
+/// ```
+pub fn decompress<T: Copy>(src: &Buffer<T>) -> Result<Vec<T>> {
+    // The element count was recorded at compression time, so there's no need
+    // to read it from the header.
+    unsafe { decompress_nelem(checked_frame(&src.data[..])?, src.nelem) }
+}
+
+/// Decompress the output of [`Context::compress_bools`].
+///
+/// Returns [`BloscError::ValidationError`] if the buffer wasn't created by
+/// [`Context::compress_bools`].
+pub fn decompress_bools(src: &Buffer<u8>) -> Result<Vec<bool>> {
+    let word = mem::size_of::<u64>();
+    let packed = decompress(src)?;
+    if packed.len() < word {
+        return Err(BloscError::ValidationError);
+    }
+    let mut len = [0u8; 8];
+    len.copy_from_slice(&packed[..word]);
+    let len = u64::from_le_bytes(len) as usize;
+    // There should be no more than one partial byte
+    let nbits = (packed.len() - word).saturating_mul(8);
+    if len > nbits || len + 8 <= nbits {
+        return Err(BloscError::ValidationError);
+    }
+    Ok((0..len)
+        .map(|i| packed[word + i / 8] & (1 << (i % 8)) != 0)
+        .collect())
+}
+
+/// Decompress a `blosc::Buffer` into a newly allocated boxed slice.
+///
+/// This is the same as [`decompress`], but the result has no spare capacity
+/// to track, which suits long-lived storage.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let data: Vec<u16> = vec![1, 2, 3, 65535];
+/// let compressed = Context::new().compress(&data[..]);
+/// let decompressed: Box<[u16]> = decompress_boxed(&compressed).unwrap();
+/// assert_eq!(&data[..], &decompressed[..]);
+/// ```
+pub fn decompress_boxed<T: Copy>(src: &Buffer<T>) -> Result<Box<[T]>> {
+    // decompress allocates exactly the right capacity, so this won't realloc
+    decompress(src).map(Vec::into_boxed_slice)
+}
+
+/// Decompress a `blosc::Buffer` into an existing `Vec`, reusing its
+/// allocation.
+///
+/// Any previous contents of `dest` are discarded, and it grows if necessary.
+/// If decompression fails, `dest` is left empty.  This is the counterpart of
+/// [`Context::compress_reuse`].
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let ctx = Context::new();
+/// let mut dest = Vec::new();
+/// decompress_reuse(&ctx.compress(&[1u32, 2, 3][..]), &mut dest).unwrap();
+/// assert_eq!(vec![1, 2, 3], dest);
+/// decompress_reuse(&ctx.compress(&[4u32, 5][..]), &mut dest).unwrap();
+/// assert_eq!(vec![4, 5], dest);
+/// ```
+pub fn decompress_reuse<T: Copy>(src: &Buffer<T>, dest: &mut Vec<T>) -> Result<()> {
+    dest.clear();
+    let frame = checked_frame(&src.data[..])?;
+    dest.reserve(src.nelem);
+    unsafe {
+        decompress_to_ptr(frame, dest.as_mut_ptr(), src.nelem)?;
+        dest.set_len(src.nelem);
+    }
+    Ok(())
+}
+
+/// Decompress arbitrary data into a newly allocated `Vec`
+///
+/// Use this method when decompressing serialized data from disk, or receiving
+/// it over the network.  Like [`decompress`], it allocates the `Vec` exactly
+/// once, sized according to the buffer's header.
+///
+/// If the buffer's compressor isn't enabled in this build of C-Blosc, the
+/// error is [`BloscError::CompressorUnavailable`], which names it.  That
+/// distinguishes a missing codec from corrupt data.
+///
+/// # Safety
+///
+/// This function is `unsafe` because it can transmute data into an arbitrary
+/// type.  That can cause memory errors if the type parameter contains
+/// references or pointers.  To use safely, the caller must ensure that the
+/// serialized data really was created by Blosc, with the correct type.
+///
+/// This function is also unsafe if the compressed buffer is untrusted.  See
+/// [Blosc issue #229](https://github.com/Blosc/c-blosc/issues/229).  A buffer
+/// that is shorter than its header claims, like a partially written file, is
+/// detected and rejected with [`BloscError::Truncated`].
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let serialized: Vec<u8> = vec![2, 1, 19, 4, 12, 0, 0, 0, 12, 0, 0, 0,
+///     28, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0];
+/// let decompressed = unsafe{ decompress_bytes(&serialized[..])}.unwrap();
+/// assert_eq!(&[1, 2, 3], &decompressed[..]);
+/// ```
+pub unsafe fn decompress_bytes<T: Copy>(src: &[u8]) -> Result<Vec<T>> {
+    decompress_bytes_limited(src, usize::MAX)
+}
+
+/// Like [`decompress_bytes`], but refuse to decompress more than `max_bytes`.
+///
+/// The uncompressed size claimed by `src`'s header is checked against
+/// `max_bytes` before anything is allocated.  That turns a maliciously large
+/// size claim into an error rather than a huge allocation.
+///
+/// # Safety
+///
+/// The same requirements apply as for [`decompress_bytes`].  Limiting the
+/// allocation size does not make it safe to decompress untrusted buffers.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let data: Vec<u32> = vec![1, 2, 3, 4];
+/// let compressed = Context::new().compress(&data[..]);
+/// let r = unsafe { decompress_bytes_limited::<u32>(compressed.as_ref(), 8) };
+/// assert_eq!(Err(BloscError::SizeLimitExceeded), r);
+/// ```
+pub unsafe fn decompress_bytes_limited<T: Copy>(src: &[u8], max_bytes: usize) -> Result<Vec<T>> {
+    let typesize = mem::size_of::<T>();
+    let (nbytes, cbytes, _blocksize) = cbuffer_sizes(src)?;
+    if src.len() < cbytes {
+        return Err(BloscError::Truncated);
+    }
+    if nbytes > max_bytes {
+        return Err(BloscError::SizeLimitExceeded);
+    }
+    if nbytes % typesize != 0 {
+        return Err(BloscError::TypesizeMismatch);
+    }
+    decompress_nelem(src, nbytes / typesize)
+}
+
+/// Like [`decompress_bytes`], but check `src` as thoroughly as possible first.
+///
+/// C-Blosc 1.x has no internal integrity checks that can be enabled during
+/// decompression, so this checks what it can beforehand:
+///
+/// * The header must pass [`is_blosc`], or [`BloscError::ValidationError`]
+///   is returned.
+/// * Every block's starting offset must lie within the buffer, after the
+///   table of offsets, or [`BloscError::CorruptData`] is returned.
+/// * If the buffer has a [`Checksum`], it must match, or
+///   [`BloscError::ChecksumMismatch`] is returned.
+///
+/// The compressed blocks themselves can only be verified by a checksum.  So
+/// for untrusted data, compress with [`Context::with_checksum`] and check
+/// that the buffer has one, with [`Context::from_buffer`].
+///
+/// # Safety
+///
+/// These checks make corruption much less likely to go unnoticed, but they
+/// don't make it safe to decompress a maliciously crafted buffer.  The same
+/// requirements apply as for [`decompress_bytes`].
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let data: Vec<u32> = (0..1000).collect();
+/// let ctx = Context::new().with_checksum(Checksum::Crc32c);
+/// let mut compressed: Vec<u8> = ctx.compress(&data[..]).into();
+/// let decompressed = unsafe { decompress_bytes_verified::<u32>(&compressed[..]) };
+/// assert_eq!(data, decompressed.unwrap());
+///
+/// let last = compressed.len() - 5;
+/// compressed[last] ^= 1;
+/// let r = unsafe { decompress_bytes_verified::<u32>(&compressed[..]) };
+/// assert_eq!(Err(BloscError::ChecksumMismatch), r);
+/// ```
+pub unsafe fn decompress_bytes_verified<T: Copy>(src: &[u8]) -> Result<Vec<T>> {
+    let (nbytes, cbytes, blocksize) = cbuffer_sizes(src)?;
+    if src.len() < cbytes {
+        return Err(BloscError::Truncated);
+    }
+    if !is_blosc(src) {
+        return Err(BloscError::ValidationError);
+    }
+    if nbytes > 0 && src[2] & BLOSC_MEMCPYED as u8 == 0 {
+        let nblocks = nbytes / blocksize + usize::from(nbytes % blocksize > 0);
+        let data_start = BLOSC_MIN_HEADER_LENGTH as usize + nblocks * 4;
+        let bstarts = &src[BLOSC_MIN_HEADER_LENGTH as usize..data_start];
+        for bstart in bstarts.chunks(4) {
+            let mut offset = [0u8; 4];
+            offset.copy_from_slice(bstart);
+            let offset = u32::from_le_bytes(offset) as usize;
+            if offset < data_start || offset >= cbytes {
+                return Err(BloscError::CorruptData);
+            }
+        }
+    }
+    decompress_bytes(checked_frame(src)?)
+}
+
+/// Like [`decompress_bytes`], but for callers that already know the element
+/// count, like from their own index.
+///
+/// Only the header's uncompressed size is read, to check `nelem` before
+/// anything is allocated.  The rest of the header is left to C-Blosc, which
+/// matters only for very small buffers, decompressed at very high rates.  If
+/// `nelem` is wrong, [`BloscError::LengthMismatch`] is returned.  A [`Buffer`]
+/// already records its element count, so [`decompress`] needs no such help.
+///
+/// # Safety
+///
+/// The same requirements apply as for [`decompress_bytes`].  In addition,
+/// `src` must be complete, because a truncated buffer isn't detected, and any
+/// checksum is ignored.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// let decompressed = unsafe { decompress_bytes_unchecked::<u32>(compressed.as_ref(), 3) };
+/// assert_eq!(vec![1, 2, 3], decompressed.unwrap());
+/// let wrong = unsafe { decompress_bytes_unchecked::<u32>(compressed.as_ref(), 4) };
+/// assert_eq!(Err(BloscError::LengthMismatch), wrong);
+/// ```
+pub unsafe fn decompress_bytes_unchecked<T: Copy>(src: &[u8], nelem: usize) -> Result<Vec<T>> {
+    decompress_nelem(src, nelem)
+}
+
+/// Like [`decompress_bytes`], but also return the typesize recorded in the
+/// buffer's header.
+///
+/// That's usually the size of the original element type, so it can recover
+/// the original element count even when decompressing as bytes.  Buffers
+/// compressed with a typesize larger than 255 record a typesize of 1.
+///
+/// # Safety
+///
+/// The same requirements apply as for [`decompress_bytes`].
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// let (bytes, typesize) =
+///     unsafe { decompress_bytes_with_len::<u8>(compressed.as_ref()) }.unwrap();
+/// assert_eq!(12, bytes.len());
+/// assert_eq!(3, bytes.len() / typesize);
+/// ```
+pub unsafe fn decompress_bytes_with_len<T: Copy>(src: &[u8]) -> Result<(Vec<T>, usize)> {
+    let dest = decompress_bytes(src)?;
+    let (typesize, _flags) = cbuffer_metainfo(src)?;
+    Ok((dest, typesize))
+}
+
+/// Decompress a buffer that was compressed by a `Context` with an explicit
+/// byte order, converting its elements back to the host's byte order.
+///
+/// The element size comes from the buffer's typesize.  See
+/// [`Context::big_endian`].
+///
+/// # Safety
+///
+/// `order` must be the byte order that the buffer was compressed with.
+/// Otherwise, the bytes of each element will be reversed, which may not be a
+/// valid value of `T`.
+pub unsafe fn decompress_with_byte_order<T: Copy>(
+    src: &Buffer<T>,
+    order: ByteOrder,
+) -> Result<Vec<T>> {
+    let mut dest = decompress(src)?;
+    if !order.is_native() {
+        let (typesize, _flags) = cbuffer_metainfo(&src.data[..])?;
+        let bytes =
+            slice::from_raw_parts_mut(dest.as_mut_ptr() as *mut u8, mem::size_of_val(&dest[..]));
+        swap_elements(bytes, typesize);
+    }
+    Ok(dest)
+}
+
+/// Decompress a `blosc::Buffer` into caller-provided storage, and return an
+/// [`ndarray`] view of it with the given shape.
+///
+/// `storage` must be at least as long as the buffer, and the shape must have
+/// exactly as many elements as the buffer.  Otherwise,
+/// [`BloscError::LengthMismatch`] is returned.  Requires the `ndarray`
+/// feature.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3, 4, 5, 6][..]);
+/// let mut storage = [0u32; 6];
+/// let view = decompress_into_array_view(&compressed, &mut storage, (2, 3)).unwrap();
+/// assert_eq!(6, view[[1, 2]]);
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn decompress_into_array_view<'a, T, D, Sh>(
+    src: &Buffer<T>,
+    storage: &'a mut [T],
+    shape: Sh,
+) -> Result<ndarray::ArrayViewMut<'a, T, D>>
+where
+    T: Copy,
+    D: ndarray::Dimension,
+    Sh: Into<ndarray::StrideShape<D>>,
+{
+    let frame = checked_frame(&src.data[..])?;
+    let storage = storage
+        .get_mut(..src.nelem)
+        .ok_or(BloscError::LengthMismatch)?;
+    let shape = shape.into();
+    if shape.size() != src.nelem {
+        return Err(BloscError::LengthMismatch);
+    }
+    // Overwriting initialized elements is fine because T: Copy
+    unsafe { decompress_to_ptr(frame, storage.as_mut_ptr(), src.nelem)? };
+    ndarray::ArrayViewMut::from_shape(shape, storage).map_err(|_| BloscError::LengthMismatch)
+}
+
+/// Decompress a `blosc::Buffer` into a [`bytes::Bytes`], for use with the
+/// `bytes` ecosystem.
+///
+/// The result holds the decompressed contents as raw bytes, whatever `T` is.
+/// It takes ownership of the decompressed allocation, so there's no extra
+/// copy.  Requires the `bytes` feature.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// use bytes::Buf;
+///
+/// let compressed = Context::new().compress(&[1u16, 2, 3][..]);
+/// let mut decompressed = decompress_to_bytes(&compressed).unwrap();
+/// assert_eq!(6, decompressed.remaining());
+/// assert_eq!(1, decompressed.get_u16_ne());
+/// ```
+#[cfg(feature = "bytes")]
+pub fn decompress_to_bytes<T>(src: &Buffer<T>) -> Result<bytes::Bytes> {
+    let frame = checked_frame(&src.data[..])?;
+    // Any data may be viewed as bytes
+    let nbytes = src
+        .nelem
+        .checked_mul(mem::size_of::<T>())
+        .ok_or(BloscError::LengthMismatch)?;
+    let bytes: Vec<u8> = unsafe { decompress_nelem(frame, nbytes)? };
+    Ok(bytes.into())
+}
+
+/// Decompress a `blosc::Buffer` into a `Vec` that uses a custom allocator.
+///
+/// This is the same as [`decompress`], except for the allocator.  Because the
+/// standard library's allocator API isn't stable yet, it uses the polyfill
+/// from [`allocator_api2`], and requires the `allocator-api2` feature.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// use allocator_api2::alloc::Global;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// let decompressed = decompress_in(&compressed, Global).unwrap();
+/// assert_eq!(&[1, 2, 3], &decompressed[..]);
+/// ```
+#[cfg(feature = "allocator-api2")]
+pub fn decompress_in<T, A>(src: &Buffer<T>, alloc: A) -> Result<allocator_api2::vec::Vec<T, A>>
+where
+    T: Copy,
+    A: allocator_api2::alloc::Allocator,
+{
+    let frame = checked_frame(&src.data[..])?;
+    let mut dest = allocator_api2::vec::Vec::with_capacity_in(src.nelem, alloc);
+    unsafe {
+        decompress_to_ptr(frame, dest.as_mut_ptr(), src.nelem)?;
+        // Unsafe if T contains references or pointers
+        dest.set_len(src.nelem);
+    }
+    Ok(dest)
+}
+
+/// Assert that `data` survives being compressed with `ctx` and decompressed.
+///
+/// This is the invariant that the crate's own tests check, and is useful in
+/// downstream tests and fuzzers.  It also checks that the buffer's header
+/// records the right uncompressed size.  Requires the `testutil` feature.
+///
+/// # Panics
+///
+/// Panics, with a message naming `ctx`, if decompression fails or the data
+/// differs.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let ctx = Context::new().shuffle(ShuffleMode::Bit);
+/// assert_round_trip(&ctx, &[1.5f64, 2.5, -0.0][..]);
+/// ```
+#[cfg(feature = "testutil")]
+pub fn assert_round_trip<T>(ctx: &Context, data: &[T])
+where
+    T: Copy + PartialEq + fmt::Debug,
+{
+    let compressed = ctx.compress(data);
+    let nbytes = compressed
+        .validate()
+        .unwrap_or_else(|e| panic!("Invalid buffer compressed with {:?}: {}", ctx, e));
+    assert_eq!(
+        mem::size_of_val(data),
+        nbytes,
+        "Wrong uncompressed size in buffer compressed with {:?}",
+        ctx
+    );
+    let decompressed = decompress(&compressed).unwrap_or_else(|e| {
+        panic!(
+            "Failed to decompress buffer compressed with {:?}: {}",
+            ctx, e
+        )
+    });
+    assert_eq!(data, &decompressed[..], "Round trip failed with {:?}", ctx);
+}
+
+/// Decompress several `blosc::Buffer`s into a single newly allocated `Vec`.
+///
+/// This is the same as decompressing each buffer and concatenating the
+/// results, but the output is allocated only once.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let ctx = Context::new();
+/// let a = ctx.compress(&[1u32, 2, 3][..]);
+/// let b = ctx.compress(&[4u32, 5][..]);
+/// assert_eq!(vec![1, 2, 3, 4, 5], decompress_concat(&[&a, &b]).unwrap());
+/// ```
+pub fn decompress_concat<T: Copy>(bufs: &[&Buffer<T>]) -> Result<Vec<T>> {
+    let total = bufs.iter().map(|buf| buf.nelem).sum();
+    let mut dest: Vec<T> = Vec::with_capacity(total);
+    for buf in bufs {
+        let frame = checked_frame(&buf.data[..])?;
+        unsafe {
+            let end = dest.as_mut_ptr().add(dest.len());
+            decompress_to_ptr(frame, end, buf.nelem)?;
+            // Unsafe if T contains references or pointers
+            dest.set_len(dest.len() + buf.nelem);
+        }
+    }
+    Ok(dest)
+}
+
+/// Decompress the output of [`Context::compress_or_store`] into a newly
+/// allocated `Vec`.
+pub fn decompress_stored<T: Copy>(src: &Stored<T>) -> Result<Vec<T>> {
+    match src {
+        Stored::Compressed(buf) => decompress(buf),
+        Stored::Raw(v) => Ok(v.clone()),
+    }
+}
+
+/// Decompress a buffer of UTF-8 bytes into a `String`.
+///
+/// This is the inverse of [`Context::compress_str`].  Returns
+/// [`BloscError::InvalidUtf8`] if the bytes aren't valid UTF-8.
+pub fn decompress_string(src: &Buffer<u8>) -> Result<String> {
+    String::from_utf8(decompress(src)?).map_err(|_| BloscError::InvalidUtf8)
+}
+
+/// Decompress a `blosc::Buffer`, refusing to return more than `max_elems`
+/// elements.
+///
+/// This is the element-oriented counterpart of [`decompress_bytes_limited`],
+/// for callers whose downstream arrays have a fixed maximum length.  The
+/// length is checked against the buffer's header before anything is
+/// allocated, and [`BloscError::SizeLimitExceeded`] is returned if it's too
+/// long.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// assert_eq!(Ok(vec![1, 2, 3]), decompress_capped(&compressed, 3));
+/// assert_eq!(Err(BloscError::SizeLimitExceeded), decompress_capped(&compressed, 2));
+/// ```
+pub fn decompress_capped<T: Copy>(src: &Buffer<T>, max_elems: usize) -> Result<Vec<T>> {
+    let nbytes = src.sizes().0;
+    if nbytes > max_elems.saturating_mul(mem::size_of::<T>()) {
+        return Err(BloscError::SizeLimitExceeded);
+    }
+    decompress(src)
+}
+
+/// Decompress a `blosc::Buffer`, insisting that it has exactly
+/// `expected_elems` elements.
+///
+/// Use this when the array's length was recorded separately from the
+/// compressed data.  The length is checked against the buffer's header
+/// before anything is decompressed, and [`BloscError::LengthMismatch`] is
+/// returned if they differ.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// assert_eq!(Ok(vec![1, 2, 3]), decompress_exact(&compressed, 3));
+/// assert_eq!(Err(BloscError::LengthMismatch), decompress_exact(&compressed, 4));
+/// ```
+pub fn decompress_exact<T: Copy>(src: &Buffer<T>, expected_elems: usize) -> Result<Vec<T>> {
+    let nbytes = src.sizes().0;
+    if expected_elems.checked_mul(mem::size_of::<T>()) != Some(nbytes) {
+        return Err(BloscError::LengthMismatch);
+    }
+    decompress(src)
+}
+
+/// Decompress only the first `n` elements of a `blosc::Buffer`.
+///
+/// If the buffer holds fewer than `n` elements, all of them are returned.
+/// Only the blocks containing the requested elements are decompressed, so this
+/// is much faster than [`decompress`] for previewing large arrays.  The
+/// buffer's checksum, if it has one, is not verified.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let data: Vec<u32> = (0..1_000_000).collect();
+/// let compressed = Context::new().compress(&data[..]);
+/// assert_eq!(vec![0, 1, 2], decompress_prefix(&compressed, 3).unwrap());
+/// ```
+pub fn decompress_prefix<T: Copy>(src: &Buffer<T>, n: usize) -> Result<Vec<T>> {
+    src.as_ref_buffer().decompress_prefix(n)
+}
+
+/// Implementation of [`decompress_prefix`], for owned and borrowed buffers.
+fn decompress_prefix_ref<T: Copy>(src: BufferRef<'_, T>, n: usize) -> Result<Vec<T>> {
+    let typesize = cbuffer_metainfo(src.data)?.0;
+    let nelem = n.min(src.nelem);
+    let dest_size = nelem * mem::size_of::<T>();
+    if dest_size == 0 {
+        return Ok(Vec::new());
+    }
+    // blosc_getitem counts in units of the header's typesize, which may
+    // differ from T's size if the Context's typesize was overridden.
+    let nitems = dest_size / typesize;
+    if nitems * typesize != dest_size || nitems > c_int::MAX as usize {
+        let mut all = src.decompress()?;
+        all.truncate(nelem);
+        return Ok(all);
+    }
+    let mut dest: Vec<T> = Vec::with_capacity(nelem);
+    let rsize = unsafe {
+        blosc_getitem(
+            src.data.as_ptr() as *const c_void,
+            0,
+            nitems as c_int,
+            dest.as_mut_ptr() as *mut c_void,
+        )
+    };
+    if rsize > 0 && rsize as usize == dest_size {
+        unsafe { dest.set_len(nelem) };
+        Ok(dest)
+    } else {
+        Err(diagnose_decompress_failure(src.data))
     }
 }
 
-/// Decompress a `blosc::Buffer` into a newly allocated `Vec`
+/// Decompress `src`, which must contain exactly `nelem` elements of type `T`.
 ///
 /// # Safety
 ///
-/// `decompress` is safe to use because the compiler will guarantee that `src`
-/// came from the output of `Context::compress`.
+/// The same requirements apply as for [`decompress_bytes`].
+unsafe fn decompress_nelem<T: Copy>(src: &[u8], nelem: usize) -> Result<Vec<T>> {
+    // Check nelem against the header before allocating, in case it came from
+    // an untrusted source
+    let (nbytes, _, _) = cbuffer_sizes(src)?;
+    if nelem.checked_mul(mem::size_of::<T>()) != Some(nbytes) {
+        return Err(BloscError::LengthMismatch);
+    }
+    let mut dest: Vec<T> = Vec::with_capacity(nelem);
+    decompress_to_ptr(src, dest.as_mut_ptr(), nelem)?;
+    // Unsafe if T contains references or pointers
+    dest.set_len(nelem);
+    Ok(dest)
+}
+
+/// Decompress exactly `nelem` elements into `dest`, which must have room for
+/// them.
+unsafe fn decompress_to_ptr<T: Copy>(src: &[u8], dest: *mut T, nelem: usize) -> Result<()> {
+    let dest_size = nelem
+        .checked_mul(mem::size_of::<T>())
+        .ok_or(BloscError::LengthMismatch)?;
+    if dest_size == 0 {
+        // C-Blosc reports an empty result the same way as an error
+        return Ok(());
+    }
+    // Unsafe if src comes from an untrusted source.
+    let rsize = blosc_decompress_ctx(
+        src.as_ptr() as *const c_void,
+        dest as *mut c_void,
+        dest_size,
+        1,
+    );
+    if rsize > 0 && rsize as usize == dest_size {
+        Ok(())
+    } else {
+        Err(diagnose_decompress_failure(src))
+    }
+}
+
+/// Figure out why C-Blosc failed to decompress `src`.
+///
+/// C-Blosc doesn't say why decompression failed, so check the likely causes
+/// after the fact.
+fn diagnose_decompress_failure(src: &[u8]) -> BloscError {
+    if matches!(flags(src), Ok(flags) if flags.memcpyed()) {
+        // No compressor was needed
+        return BloscError::CorruptData;
+    }
+    // All compressors from the same library are supported together, so it
+    // doesn't matter that the header can't distinguish LZ4 from LZ4HC.
+    match Compressor::from_header(src) {
+        Some(compressor) if compressor.supported() => BloscError::CorruptData,
+        Some(compressor) => BloscError::CompressorUnavailable(compressor),
+        None => BloscError::CompressorNotSupported,
+    }
+}
+
+/// Decompress a `blosc::SoaBuffer` back into an array of structs.
+///
+/// Any padding bytes in the structs will be zero.
+pub fn decompress_soa<T: Columnar>(src: &SoaBuffer<T>) -> Result<Vec<T>> {
+    let word = mem::size_of::<u64>();
+    let mut nelem = [0u8; 8];
+    nelem.copy_from_slice(src.data.get(..word).ok_or(BloscError::ValidationError)?);
+    let nelem = u64::from_le_bytes(nelem) as usize;
+    let reader = ColumnReader::new(&src.data[word..])?;
+    if reader.len() != T::FIELDS.len() {
+        return Err(BloscError::ValidationError);
+    }
+    let mut columns = Vec::with_capacity(reader.len());
+    for (i, &(_offset, size)) in T::FIELDS.iter().enumerate() {
+        // Safe because each column was compressed as bytes
+        let column = unsafe { reader.decompress::<u8>(i)? };
+        if Some(column.len()) != nelem.checked_mul(size) {
+            return Err(BloscError::ValidationError);
+        }
+        columns.push(column);
+    }
+    let elem_size = mem::size_of::<T>();
+    let total = nelem
+        .checked_mul(elem_size)
+        .ok_or(BloscError::ValidationError)?;
+    let mut dest: Vec<T> = Vec::with_capacity(nelem);
+    unsafe {
+        let base = dest.as_mut_ptr() as *mut u8;
+        ptr::write_bytes(base, 0, total);
+        for (column, &(offset, size)) in columns.iter().zip(T::FIELDS) {
+            for i in 0..nelem {
+                let field = base.add(i * elem_size + offset);
+                ptr::copy_nonoverlapping(column.as_ptr().add(i * size), field, size);
+            }
+        }
+        // Unsafe if the fields don't cover all of T
+        dest.set_len(nelem);
+    }
+    Ok(dest)
+}
+
+/// Decompress a `blosc::RaggedBuffer` back into its original rows.
+///
+/// Returns [`BloscError::TypesizeMismatch`] if `T` is zero-sized, because the
+/// row lengths can't be recovered from the decompressed size.
+pub fn decompress_ragged<T: Copy>(src: &RaggedBuffer<T>) -> Result<Vec<Vec<T>>> {
+    if mem::size_of::<T>() == 0 {
+        return Err(BloscError::TypesizeMismatch);
+    }
+    let word = mem::size_of::<u64>();
+    let read_u64 = |i: usize| -> Result<usize> {
+        let bytes = src.data.get(i * word..(i + 1) * word);
+        let bytes = bytes.ok_or(BloscError::ValidationError)?;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(buf) as usize)
+    };
+    let nrows = read_u64(0)?;
+    let lengths = (1..=nrows).map(read_u64).collect::<Result<Vec<_>>>()?;
+    let flat = unsafe { decompress_bytes::<T>(&src.data[(nrows + 1) * word..])? };
+    if lengths.iter().sum::<usize>() != flat.len() {
+        return Err(BloscError::ValidationError);
+    }
+    let mut rest = &flat[..];
+    let mut rows = Vec::with_capacity(nrows);
+    for len in lengths {
+        let (row, tail) = rest.split_at(len);
+        rows.push(row.to_vec());
+        rest = tail;
+    }
+    Ok(rows)
+}
+
+/// The split mode last set by [`set_global_splitmode`].
+///
+/// C-Blosc reads its global split mode during every compression, so
+/// compressions hold this lock for reading, and anything that changes the
+/// global mode holds it for writing.
+fn global_split_mode() -> &'static RwLock<c_int> {
+    static INIT: Once = Once::new();
+    // RwLock::new isn't const until Rust 1.63
+    static mut LOCK: *const RwLock<c_int> = ptr::null();
+    unsafe {
+        INIT.call_once(|| {
+            let lock = RwLock::new(BLOSC_FORWARD_COMPAT_SPLIT as c_int);
+            LOCK = Box::into_raw(Box::new(lock));
+        });
+        &*LOCK
+    }
+}
+
+/// Set C-Blosc's process-wide split mode.
+///
+/// This affects C-Blosc's global state, so it also applies to any C code in
+/// the process that uses C-Blosc's simple API.  It applies to every `Context`
+/// that doesn't set its own [`split_mode`](Context::split_mode).  The default
+/// is [`SplitMode::ForwardCompat`].
+///
+/// # Thread safety
+///
+/// This waits for any compressions from this crate that are in progress in
+/// other threads.  C code in the same process that uses C-Blosc directly isn't
+/// synchronized.
+pub fn set_global_splitmode(mode: SplitMode) {
+    let mut global = global_split_mode()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    *global = mode as c_int;
+    unsafe { blosc_set_splitmode(*global) };
+}
+
+/// The compressor last set by [`set_default_compressor`].
+static DEFAULT_COMPRESSOR: AtomicU8 = AtomicU8::new(BLOSC_BLOSCLZ as u8);
+
+/// Choose the compressor used by [`Context::default`], for the whole process.
+///
+/// This lets one setting apply throughout a large program.  It doesn't affect
+/// [`Context::new`], or any existing `Context`.  The initial default is
+/// `BloscLZ`.
+///
+/// Returns an error if the `compressor` is not enabled in this build of
+/// C-Blosc.
 ///
 /// # Example
-/// The compiler won't allow decompressing into the wrong type.
+/// ```
+/// # use blosc::*;
+/// set_default_compressor(Compressor::Zstd).unwrap();
+/// let ctx = Context::default();
+/// assert_eq!(ctx, Context::new().compressor(Compressor::Zstd).unwrap());
+/// ```
+pub fn set_default_compressor(compressor: Compressor) -> Result<()> {
+    if compressor.supported() {
+        DEFAULT_COMPRESSOR.store(compressor.to_id(), Ordering::Relaxed);
+        Ok(())
+    } else {
+        Err(BloscError::CompressorNotSupported)
+    }
+}
+
+/// Return the largest typesize that C-Blosc's shuffle filters support.
 ///
-/// ```compile_fail
+/// Larger typesizes are recorded in the header as 1, and their data is never
+/// shuffled.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// assert_eq!(255, max_typesize());
+/// ```
+pub const fn max_typesize() -> usize {
+    BLOSC_MAX_TYPESIZE as usize
+}
+
+/// Report which SIMD shuffle implementations are available, for diagnosing
+/// performance differences between machines.
+///
+/// C-Blosc has no way to query its build configuration.  With the `static`
+/// feature, the bundled C-Blosc is built with exactly the SSE2 and AVX2
+/// support that this crate's target enables, so that's reported.  A system
+/// libblosc's configuration is unknown, though its default build includes
+/// both.  The CPU's support is detected at runtime, and is always `false` on
+/// architectures other than x86.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let info = simd_info();
+/// if info.avx2_compiled == Some(true) && !info.avx2_cpu {
+///     eprintln!("This CPU can't use C-Blosc's AVX2 shuffle");
+/// }
+/// ```
+pub fn simd_info() -> SimdInfo {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    let (sse2_cpu, avx2_cpu) = (
+        is_x86_feature_detected!("sse2"),
+        is_x86_feature_detected!("avx2"),
+    );
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    let (sse2_cpu, avx2_cpu) = (false, false);
+    // blosc-src enables each implementation if the Rust target does
+    let compiled = |enabled: bool| cfg!(feature = "static").then(|| enabled);
+    SimdInfo {
+        sse2_compiled: compiled(cfg!(target_feature = "sse2")),
+        avx2_compiled: compiled(cfg!(target_feature = "avx2")),
+        sse2_cpu,
+        avx2_cpu,
+    }
+}
+
+/// Suggest a blocksize for compressing `nbytes` of data.
+///
+/// This replicates the heuristic that C-Blosc uses when the `Context`'s
+/// blocksize is `None`, assuming a fast codec like `BloscLZ`, `LZ4`, or
+/// `Snappy` and the default split mode.  It's advisory only; the actual
+/// heuristic may differ between C-Blosc versions, and it chooses larger blocks
+/// for the high compression codecs `LZ4HC`, `Zlib`, and `Zstd`.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// assert_eq!(262144, suggest_blocksize(Clevel::L2, 8, 1 << 20));
+/// ```
+pub fn suggest_blocksize(clevel: Clevel, typesize: usize, nbytes: usize) -> usize {
+    const L1: usize = 32 * 1024;
+    const MIN_BUFFERSIZE: usize = 128;
+    const MAX_SPLITS: usize = 16;
+
+    // C-Blosc treats oversized types as a stream of bytes
+    let typesize = if typesize > BLOSC_MAX_TYPESIZE as usize {
+        1
+    } else {
+        typesize.max(1)
+    };
+    if nbytes < typesize {
+        return 1;
+    }
+    let mut blocksize = nbytes;
+    if nbytes >= L1 {
+        blocksize = match clevel {
+            Clevel::None => L1 / 4,
+            Clevel::L1 => L1 / 2,
+            Clevel::L2 => L1,
+            Clevel::L3 => L1 * 2,
+            Clevel::L4 | Clevel::L5 => L1 * 4,
+            Clevel::L6 | Clevel::L7 | Clevel::L8 | Clevel::L9 => L1 * 8,
+        };
+    }
+    // Enlarge the blocksize for codecs that split blocks
+    let split = typesize <= MAX_SPLITS && blocksize / typesize >= MIN_BUFFERSIZE;
+    if clevel > Clevel::None && split {
+        blocksize = (blocksize.min(1 << 18) * typesize).clamp(1 << 16, 1 << 20);
+    }
+    blocksize = blocksize.min(nbytes);
+    if blocksize > typesize {
+        blocksize = blocksize / typesize * typesize;
+    }
+    blocksize
+}
+
+/// Formats a byte count with IEC binary units, like `1.2 MiB`.
+struct IecBytes(usize);
+
+impl fmt::Display for IecBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if value < 10.0 {
+            write!(f, "{:.1} {}", value, UNITS[unit])
+        } else {
+            write!(f, "{:.0} {}", value, UNITS[unit])
+        }
+    }
+}
+
+/// Return the largest possible size of a Blosc buffer holding `src_size` bytes,
+/// without any checksum.
+///
+/// Returns [`BloscError::SizeLimitExceeded`] if that's too much for one buffer.
+/// Checked arithmetic keeps the result from wrapping on 32-bit targets.
+fn max_frame_len(src_size: usize) -> Result<usize> {
+    if src_size > BLOSC_MAX_BUFFERSIZE as usize {
+        return Err(BloscError::SizeLimitExceeded);
+    }
+    src_size
+        .checked_add(BLOSC_MAX_OVERHEAD as usize)
+        .ok_or(BloscError::SizeLimitExceeded)
+}
+
+/// Guess whether `bytes` were already shuffled with `typesize`.  See
+/// [`Context::lint_data`].
+fn looks_shuffled(bytes: &[u8], typesize: usize) -> bool {
+    let len = bytes.len();
+    if len < 64 * typesize {
+        return false;
+    }
+    // Each byte plane of shuffled data looks different, so sample windows
+    // spread over all of them.
+    let window = (16 * typesize).max(1024);
+    let windows: Vec<&[u8]> = if len <= LINT_SAMPLE_LEN {
+        vec![bytes]
+    } else {
+        let n = LINT_SAMPLE_LEN / window;
+        let step = (len - window) / (n - 1);
+        (0..n).map(|i| &bytes[i * step..][..window]).collect()
+    };
+    let equal = |distance: usize| -> u64 {
+        let count = |w: &&[u8]| w.iter().zip(&w[distance..]).filter(|(a, b)| a == b).count();
+        windows.iter().map(count).sum::<usize>() as u64
+    };
+    let adjacent = equal(1);
+    let strided = equal(typesize);
+    let sampled = windows.iter().map(|w| w.len()).sum::<usize>() as u64;
+    let adjacent_pairs = sampled - windows.len() as u64;
+    let strided_pairs = sampled - (windows.len() * typesize) as u64;
+    // Random or already compressed data has no structure to go by
+    if adjacent * 8 < adjacent_pairs || adjacent == adjacent_pairs {
+        return false;
+    }
+    // Adjacent bytes are equal at least 90% as often as strided ones
+    10 * adjacent * strided_pairs >= 9 * strided * adjacent_pairs
+}
+
+/// Return a buffer's Blosc frame, ready for decompression.
+///
+/// Fails if the buffer is too short, or if its checksum is wrong.
+fn checked_frame(src: &[u8]) -> Result<&[u8]> {
+    if src.len() < BLOSC_MIN_HEADER_LENGTH as usize {
+        // The Buffer has been cleared
+        return Err(BloscError::ValidationError);
+    }
+    let (frame, trailer) = frame_and_trailer(src);
+    if !trailer.is_empty() {
+        verify_trailer(src)?;
+    }
+    Ok(frame)
+}
+
+/// Split a buffer into its Blosc frame and its checksum trailer, if any.
+fn frame_and_trailer(src: &[u8]) -> (&[u8], &[u8]) {
+    let cbytes = cbuffer_sizes(src).map_or(0, |(_nbytes, cbytes, _blocksize)| cbytes);
+    src.split_at(cbytes.min(src.len()))
+}
+
+/// Read the `(nbytes, cbytes, blocksize)` fields from a Blosc header.
+///
+/// Only the fixed-size header is read, and it must be entirely present.
+fn cbuffer_sizes(src: &[u8]) -> Result<(usize, usize, usize)> {
+    if src.len() < BLOSC_MIN_HEADER_LENGTH as usize {
+        return Err(BloscError::ValidationError);
+    }
+    let mut nbytes: usize = 0;
+    let mut cbytes: usize = 0;
+    let mut blocksize: usize = 0;
+    unsafe {
+        blosc_cbuffer_sizes(
+            src.as_ptr() as *const c_void,
+            &mut nbytes as *mut usize,
+            &mut cbytes as *mut usize,
+            &mut blocksize as *mut usize,
+        );
+    }
+    Ok((nbytes, cbytes, blocksize))
+}
+
+/// Look up the name of a C-Blosc compressor code, like `"lz4"` for `1`.
+///
+/// Returns `None` if the code is unknown.  A known code's name is returned even
+/// if this build of C-Blosc doesn't support that compressor.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// assert_eq!(Some("zstd"), compcode_to_name(5));
+/// assert_eq!(None, compcode_to_name(42));
+/// ```
+pub fn compcode_to_name(code: i32) -> Option<&'static str> {
+    let mut name: *const c_char = ptr::null();
+    unsafe {
+        blosc_compcode_to_compname(code, &mut name as *mut *const c_char);
+    }
+    if name.is_null() {
+        None
+    } else {
+        // The names are static strings within C-Blosc
+        unsafe { CStr::from_ptr(name) }.to_str().ok()
+    }
+}
+
+/// Look up the C-Blosc compressor code for a compressor name, like `1` for
+/// `"lz4"`.
+///
+/// Returns `None` if the name is unknown, or if this build of C-Blosc doesn't
+/// support that compressor.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// assert_eq!(Some(0), name_to_compcode("blosclz"));
+/// assert_eq!(None, name_to_compcode("gzip"));
+/// ```
+pub fn name_to_compcode(name: &str) -> Option<i32> {
+    let name = CString::new(name).ok()?;
+    let code = unsafe { blosc_compname_to_compcode(name.as_ptr()) };
+    if code >= 0 {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+/// Read the flags from a Blosc header.
+///
+/// Only the fixed-size header is needed.  Returns an error if it's incomplete.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let ctx = Context::new().shuffle(ShuffleMode::Byte);
+/// let compressed = ctx.compress(&[0u32; 1000][..]);
+/// let flags = flags(compressed.as_ref()).unwrap();
+/// assert!(flags.byte_shuffle());
+/// assert!(!flags.bit_shuffle());
+/// assert!(!flags.memcpyed());
+/// ```
+pub fn flags(src: &[u8]) -> Result<BufferFlags> {
+    let (_typesize, flags) = cbuffer_metainfo(src)?;
+    // C-Blosc only reports the low three bits, but the split flag is useful
+    // too.
+    Ok(BufferFlags(flags as u8 | (src[2] & BLOSC_DONT_SPLIT)))
+}
+
+/// Read the `(typesize, flags)` fields from a Blosc header.
+fn cbuffer_metainfo(src: &[u8]) -> Result<(usize, c_int)> {
+    if src.len() < BLOSC_MIN_HEADER_LENGTH as usize {
+        return Err(BloscError::ValidationError);
+    }
+    let mut typesize: usize = 0;
+    let mut flags: c_int = 0;
+    unsafe {
+        blosc_cbuffer_metainfo(
+            src.as_ptr() as *const c_void,
+            &mut typesize as *mut usize,
+            &mut flags as *mut c_int,
+        );
+    }
+    Ok((typesize, flags))
+}
+
+/// Peek at the total compressed length of a Blosc buffer, header included.
+///
+/// Only the fixed-size Blosc header is needed, so this is useful for framing
+/// Blosc buffers within a byte stream.  Returns an error if `header` is shorter
+/// than the minimum header size.
+///
+/// # Example
+/// ```
 /// # use blosc::*;
 /// let data: Vec<u16> = vec![1, 2, 3, 65535];
 /// let ctx = Context::new();
 /// let compressed = ctx.compress(&data[..]);
-/// let decompressed: Vec<i16> = decompress(&compressed).unwrap();
+/// let header = &compressed.as_ref()[..16];
+/// assert_eq!(Ok(compressed.size()), compressed_len(header));
 /// ```
-pub fn decompress<T: Copy>(src: &Buffer<T>) -> Result<Vec<T>> {
-    unsafe { decompress_bytes(&src.data[..]) }
+pub fn compressed_len(header: &[u8]) -> Result<usize> {
+    cbuffer_sizes(header).map(|(_nbytes, cbytes, _blocksize)| cbytes)
 }
 
-/// Decompress arbitrary data into a newly allocated `Vec`
+/// Lazily decompress a stream of concatenated Blosc buffers.
 ///
-/// Use this method when decompressing serialized data from disk, or receiving
-/// it over the network.
+/// Each iteration reads one buffer from `reader`, using its header to learn
+/// its length, and decompresses it.  Iteration ends cleanly at the end of the
+/// stream, or after the first error.  A stream that ends partway through a
+/// buffer fails with `io::ErrorKind::UnexpectedEof`, and a buffer that can't
+/// be decompressed fails with `io::ErrorKind::InvalidData`.
+///
+/// The buffers must not have checksums, because nothing in the stream marks
+/// where a checksum ends.  The whole stream is never held in memory, so
+/// wrapping `reader` in a [`BufReader`](std::io::BufReader) is a good idea.
 ///
 /// # Safety
 ///
-/// This function is `unsafe` because it can transmute data into an arbitrary
-/// type.  That can cause memory errors if the type parameter contains
-/// references or pointers.  To use safely, the caller must ensure that the
-/// serialized data really was created by Blosc, with the correct type.
+/// The stream's contents are unchecked, so the same requirements apply as for
+/// [`decompress_bytes`].
 ///
-/// This function is also unsafe if the compressed buffer is untrusted.  See
-/// [Blosc issue #229](https://github.com/Blosc/c-blosc/issues/229).
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let ctx = Context::new();
+/// let mut log = Vec::new();
+/// log.extend_from_slice(ctx.compress(&[1u32, 2, 3][..]).as_ref());
+/// log.extend_from_slice(ctx.compress(&[4u32, 5][..]).as_ref());
+///
+/// let frames = unsafe { frames::<u32, _>(&log[..]) };
+/// let frames = frames.collect::<std::io::Result<Vec<_>>>().unwrap();
+/// assert_eq!(vec![vec![1, 2, 3], vec![4, 5]], frames);
+/// ```
+pub unsafe fn frames<T: Copy, R: Read>(reader: R) -> Frames<T, R> {
+    Frames {
+        inner: reader,
+        done: false,
+        phantom: PhantomData,
+    }
+}
+
+/// An iterator over the buffers in a stream, created by [`frames`].
+pub struct Frames<T, R> {
+    inner: R,
+    done: bool,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Copy, R: Read> Frames<T, R> {
+    /// Read and decompress the next buffer, or return `None` at the end of
+    /// the stream.
+    fn read_frame(&mut self) -> io::Result<Option<Vec<T>>> {
+        let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+        let mut header = [0u8; BLOSC_MIN_HEADER_LENGTH as usize];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.inner.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        let cbytes = compressed_len(&header[..]).map_err(invalid_data)?;
+        let rest = cbytes
+            .checked_sub(header.len())
+            .ok_or_else(|| invalid_data(BloscError::ValidationError))?;
+        let mut frame = header.to_vec();
+        // Don't trust the length enough to preallocate it
+        self.inner
+            .by_ref()
+            .take(rest as u64)
+            .read_to_end(&mut frame)?;
+        if frame.len() < cbytes {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        // frames' caller has promised that the stream is trustworthy
+        unsafe { decompress_bytes(&frame[..]) }
+            .map(Some)
+            .map_err(invalid_data)
+    }
+}
+
+impl<T: Copy, R: Read> Iterator for Frames<T, R> {
+    type Item = io::Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let r = self.read_frame();
+        if !matches!(r, Ok(Some(_))) {
+            self.done = true;
+        }
+        r.transpose()
+    }
+}
+
+/// Verify the checksum appended by [`Context::with_checksum`].
+///
+/// Returns [`BloscError::ChecksumMismatch`] if the buffer has been corrupted,
+/// or [`BloscError::ValidationError`] if it has no checksum at all.
 ///
 /// # Example
 /// ```
 /// # use blosc::*;
-/// let serialized: Vec<u8> = vec![2, 1, 19, 4, 12, 0, 0, 0, 12, 0, 0, 0,
-///     28, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0];
-/// let decompressed = unsafe{ decompress_bytes(&serialized[..])}.unwrap();
-/// assert_eq!(&[1, 2, 3], &decompressed[..]);
+/// let ctx = Context::new().with_checksum(Checksum::Crc32c);
+/// let compressed = ctx.compress(&[1u32, 2, 3][..]);
+/// assert_eq!(Ok(()), verify_checksum(&compressed));
+///
+/// let plain = Context::new().compress(&[1u32, 2, 3][..]);
+/// assert_eq!(Err(BloscError::ValidationError), verify_checksum(&plain));
 /// ```
-pub unsafe fn decompress_bytes<T: Copy>(src: &[u8]) -> Result<Vec<T>> {
-    let typesize = mem::size_of::<T>();
-    let mut nbytes: usize = 0;
-    let mut _cbytes: usize = 0;
-    let mut _blocksize: usize = 0;
-    // Unsafe if src comes from an untrusted source.
-    blosc_cbuffer_sizes(
-        src.as_ptr() as *const c_void,
-        &mut nbytes as *mut usize,
-        &mut _cbytes as *mut usize,
-        &mut _blocksize as *mut usize,
-    );
-    let dest_size = nbytes / typesize;
-    let mut dest: Vec<T> = Vec::with_capacity(dest_size);
-    // Unsafe if src comes from an untrusted source.
-    let rsize = blosc_decompress_ctx(
-        src.as_ptr() as *const c_void,
-        dest.as_mut_ptr() as *mut c_void,
-        nbytes,
-        1,
-    );
-    if rsize > 0 {
-        // Unsafe if T contains references or pointers
-        dest.set_len(rsize as usize / typesize);
-        dest.shrink_to_fit();
-        Ok(dest)
+pub fn verify_checksum<T>(src: &Buffer<T>) -> Result<()> {
+    verify_trailer(&src.data[..])
+}
+
+/// Verify the checksum trailer after a Blosc frame.
+fn verify_trailer(src: &[u8]) -> Result<()> {
+    let (frame, trailer) = frame_and_trailer(src);
+    match Checksum::from_len(trailer.len()) {
+        Some(checksum) if checksum.compute(frame) == trailer => Ok(()),
+        Some(_) => Err(BloscError::ChecksumMismatch),
+        None => Err(BloscError::ValidationError),
+    }
+}
+
+/// Guess whether `bytes` begins with a complete Blosc buffer.
+///
+/// Only the header is checked, the same way that C-Blosc checks it before
+/// decompressing: the format version, the flags, the typesize, and whether
+/// the sizes are consistent with each other and with `bytes`.  Unlike
+/// [`validate`], `bytes` may continue past the end of the buffer, which suits
+/// sniffing a stream.  It never panics, whatever the input.
+///
+/// A `true` result doesn't guarantee that decompression will succeed, or
+/// that it's safe.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// assert!(is_blosc(compressed.as_ref()));
+/// assert!(!is_blosc(b"Not a Blosc buffer"));
+/// assert!(!is_blosc(&[]));
+/// ```
+pub fn is_blosc(bytes: &[u8]) -> bool {
+    let (nbytes, cbytes, blocksize) = match cbuffer_sizes(bytes) {
+        Ok(sizes) => sizes,
+        Err(_) => return false,
+    };
+    let flags = bytes[2];
+    let typesize = bytes[3];
+    if bytes[0] != BLOSC_VERSION_FORMAT as u8
+        || flags & 0x08 != 0
+        || Compressor::from_header(bytes).is_none()
+        || typesize == 0
+        || cbytes < BLOSC_MIN_HEADER_LENGTH as usize
+        || cbytes > bytes.len()
+        || nbytes > BLOSC_MAX_BUFFERSIZE as usize
+    {
+        return false;
+    }
+    if nbytes == 0 {
+        return true;
+    }
+    if blocksize == 0 || blocksize > nbytes {
+        return false;
+    }
+    if flags & BLOSC_MEMCPYED as u8 != 0 {
+        cbytes == nbytes + BLOSC_MAX_OVERHEAD as usize
     } else {
-        // Buffer too small, data corrupted, decompressor not available, etc
-        Err(BloscError::Unspecified)
+        // There must be room for the block offsets
+        let nblocks = nbytes / blocksize + usize::from(nbytes % blocksize > 0);
+        nblocks <= (cbytes - BLOSC_MIN_HEADER_LENGTH as usize) / 4
     }
 }
 
@@ -448,7 +4094,41 @@ pub fn validate(src: &[u8]) -> Result<usize> {
 fn test_buffer_into() {
     let v0 = vec![0u8, 1, 2, 3, 4, 5];
     let v1 = v0.clone();
-    let buf = Buffer::<u16>::from_vec(v0);
+    let buf = Buffer::<u16>::from_vec(v0, 3);
     let v2: Vec<u8> = buf.into();
     assert_eq!(v1, v2);
 }
+
+#[test]
+fn test_checksum_mismatch() {
+    let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    let mut buf = ctx.compress(&[1u32, 2, 3][..]);
+    let last = buf.data.len() - 5;
+    buf.data[last] ^= 0xff;
+    assert_eq!(Err(BloscError::ChecksumMismatch), verify_checksum(&buf));
+    assert_eq!(Err(BloscError::ChecksumMismatch), decompress(&buf));
+}
+
+#[test]
+fn test_iec_bytes() {
+    assert_eq!("0 B", IecBytes(0).to_string());
+    assert_eq!("1023 B", IecBytes(1023).to_string());
+    assert_eq!("1.0 KiB", IecBytes(1024).to_string());
+    assert_eq!("1.2 MiB", IecBytes(1_258_291).to_string());
+    assert_eq!("210 KiB", IecBytes(215_040).to_string());
+    assert_eq!("3.0 GiB", IecBytes(3 << 30).to_string());
+}
+
+#[test]
+fn test_shrink() {
+    let data = [0u32; 1000];
+    let ctx = Context::new();
+    let shrunk = ctx.shrink(true).compress(&data[..]);
+    assert_eq!(shrunk.data.len(), shrunk.data.capacity());
+    assert!(shrunk.data.len() < 4000);
+    // Without shrinking, the worst-case allocation is kept
+    let unshrunk = ctx.shrink(false).compress(&data[..]);
+    assert_eq!(ctx.max_compressed_len(4000), unshrunk.data.capacity());
+    assert_eq!(shrunk.data, unshrunk.data);
+    assert_eq!(data[..], decompress(&unshrunk).unwrap()[..]);
+}
@@ -0,0 +1,82 @@
+// vim: tw=80
+//! Compressed buffers that carry their own type and length metadata.
+
+use std::{any, marker::PhantomData, mem};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{checked_frame, decompress_nelem, BloscError, Buffer, Result};
+
+/// A compressed buffer, tagged with the type and number of its elements.
+///
+/// Unlike a [`Buffer`], a `TaggedBuffer` can be serialized, and checked
+/// against the expected type when it's deserialized and decompressed.
+///
+/// # Example
+/// ```
+/// # use blosc::*;
+/// let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+/// let tagged = TaggedBuffer::from(compressed);
+/// let serialized = bincode::serialize(&tagged).unwrap();
+///
+/// let tagged: TaggedBuffer<u32> = bincode::deserialize(&serialized[..]).unwrap();
+/// assert_eq!(vec![1, 2, 3], unsafe { decompress_tagged(&tagged) }.unwrap());
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct TaggedBuffer<T> {
+    data: Vec<u8>,
+    elem_count: usize,
+    type_name: String,
+    #[serde(skip)]
+    phantom: PhantomData<T>,
+}
+
+impl<T> TaggedBuffer<T> {
+    /// Return the number of elements recorded when the buffer was tagged.
+    pub fn elem_count(&self) -> usize {
+        self.elem_count
+    }
+
+    /// Return the name of the element type recorded when the buffer was
+    /// tagged, as given by [`std::any::type_name`].
+    pub fn type_name(&self) -> &str {
+        &self.type_name[..]
+    }
+}
+
+impl<T> From<Buffer<T>> for TaggedBuffer<T> {
+    fn from(buf: Buffer<T>) -> Self {
+        TaggedBuffer {
+            elem_count: buf.nelem,
+            data: buf.data,
+            type_name: any::type_name::<T>().to_owned(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Decompress a `TaggedBuffer` into a newly allocated `Vec`.
+///
+/// Returns [`BloscError::TypeMismatch`] if the buffer was tagged with a
+/// different type than `T`, or [`BloscError::LengthMismatch`] if its element
+/// count doesn't agree with its header.
+///
+/// # Safety
+///
+/// A deserialized `TaggedBuffer` could have come from anywhere, and the type
+/// name check guards against mistakes, not malice.  So the same requirements
+/// apply as for [`decompress_bytes`](crate::decompress_bytes).  Also, the names
+/// returned by `std::any::type_name` aren't guaranteed to be stable across
+/// compiler versions.
+pub unsafe fn decompress_tagged<T: Copy>(src: &TaggedBuffer<T>) -> Result<Vec<T>> {
+    if src.type_name != any::type_name::<T>() {
+        return Err(BloscError::TypeMismatch);
+    }
+    let frame = checked_frame(&src.data[..])?;
+    let (nbytes, _cbytes, _blocksize) = crate::cbuffer_sizes(frame)?;
+    if src.elem_count.checked_mul(mem::size_of::<T>()) != Some(nbytes) {
+        return Err(BloscError::LengthMismatch);
+    }
+    decompress_nelem(frame, src.elem_count)
+}
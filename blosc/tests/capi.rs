@@ -0,0 +1,172 @@
+// vim: tw=80
+//! Tests for the C API, called from Rust.
+#![cfg(feature = "capi")]
+
+use blosc::capi::*;
+use std::{os::raw::c_void, ptr};
+
+fn compress(src: &[u32], compressor: &[u8], checksum: bool) -> (i32, Vec<u8>) {
+    let src_len = std::mem::size_of_val(src);
+    let mut dest = vec![0u8; blosc_rs_max_compressed_len(src_len)];
+    let mut written = 0;
+    let r = unsafe {
+        blosc_rs_compress(
+            src.as_ptr() as *const c_void,
+            src_len,
+            4,
+            5,
+            1,
+            compressor.as_ptr() as *const _,
+            checksum as i32,
+            dest.as_mut_ptr() as *mut c_void,
+            dest.len(),
+            &mut written,
+        )
+    };
+    dest.truncate(written);
+    (r, dest)
+}
+
+fn decompress(src: &[u8], dest_len: usize) -> (i32, Vec<u32>) {
+    let mut dest = vec![0u32; dest_len / 4];
+    let mut written = 0;
+    let r = unsafe {
+        blosc_rs_decompress(
+            src.as_ptr() as *const c_void,
+            src.len(),
+            dest.as_mut_ptr() as *mut c_void,
+            dest_len,
+            &mut written,
+        )
+    };
+    dest.truncate(written / 4);
+    (r, dest)
+}
+
+#[test]
+fn round_trip() {
+    let data: Vec<u32> = (0..1000).collect();
+    for checksum in [false, true].iter() {
+        let (r, compressed) = compress(&data[..], b"lz4\0", *checksum);
+        assert_eq!(BLOSC_RS_OK, r);
+        let mut len = 0;
+        let r = unsafe {
+            blosc_rs_decompressed_len(
+                compressed.as_ptr() as *const c_void,
+                compressed.len(),
+                &mut len,
+            )
+        };
+        assert_eq!(BLOSC_RS_OK, r);
+        assert_eq!(4000, len);
+        assert_eq!(
+            (BLOSC_RS_OK, data.clone()),
+            decompress(&compressed[..], len)
+        );
+    }
+}
+
+#[test]
+fn checksum_mismatch() {
+    let data: Vec<u32> = (0..1000).collect();
+    let (_, mut compressed) = compress(&data[..], b"lz4\0", true);
+    let last = compressed.len() - 1;
+    compressed[last] ^= 0xff;
+    let (r, _) = decompress(&compressed[..], 4000);
+    assert_eq!(BLOSC_RS_ERROR_CHECKSUM_MISMATCH, r);
+}
+
+#[test]
+fn dest_too_small() {
+    let data: Vec<u32> = (0..1000).collect();
+    let (_, compressed) = compress(&data[..], b"lz4\0", false);
+    let (r, _) = decompress(&compressed[..], 3996);
+    assert_eq!(BLOSC_RS_ERROR_DEST_TOO_SMALL, r);
+}
+
+#[test]
+fn compress_dest_too_small() {
+    let data = [0u32; 1000];
+    // Zeros compress very well, but there must be room for the worst case
+    let mut dest = vec![0u8; blosc_rs_max_compressed_len(4000) - 1];
+    let mut written = 0;
+    let r = unsafe {
+        blosc_rs_compress(
+            data.as_ptr() as *const c_void,
+            4000,
+            4,
+            5,
+            1,
+            ptr::null(),
+            1,
+            dest.as_mut_ptr() as *mut c_void,
+            dest.len(),
+            &mut written,
+        )
+    };
+    assert_eq!(BLOSC_RS_ERROR_DEST_TOO_SMALL, r);
+}
+
+#[test]
+fn truncated() {
+    let data: Vec<u32> = (0..1000).collect();
+    let (_, compressed) = compress(&data[..], b"lz4\0", false);
+    let (r, _) = decompress(&compressed[..compressed.len() - 1], 4000);
+    assert_eq!(BLOSC_RS_ERROR_INVALID_DATA, r);
+}
+
+#[test]
+fn unknown_compressor() {
+    let (r, _) = compress(&[1, 2, 3], b"lzma\0", false);
+    assert_eq!(BLOSC_RS_ERROR_INVALID_ARGUMENT, r);
+}
+
+#[test]
+fn null_pointers() {
+    let mut len = 0;
+    let r = unsafe { blosc_rs_decompressed_len(ptr::null(), 16, &mut len) };
+    assert_eq!(BLOSC_RS_ERROR_INVALID_ARGUMENT, r);
+    let mut dest = [0u8; 64];
+    let r = unsafe {
+        blosc_rs_compress(
+            ptr::null(),
+            0,
+            1,
+            5,
+            0,
+            ptr::null(),
+            0,
+            dest.as_mut_ptr() as *mut c_void,
+            dest.len(),
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(BLOSC_RS_ERROR_INVALID_ARGUMENT, r);
+}
+
+#[test]
+fn zero_typesize() {
+    let src = [1u32, 2, 3];
+    let mut dest = [0u8; 64];
+    let mut written = 0;
+    let r = unsafe {
+        blosc_rs_compress(
+            src.as_ptr() as *const c_void,
+            12,
+            0,
+            5,
+            1,
+            ptr::null(),
+            0,
+            dest.as_mut_ptr() as *mut c_void,
+            dest.len(),
+            &mut written,
+        )
+    };
+    assert_eq!(BLOSC_RS_ERROR_INVALID_ARGUMENT, r);
+}
+
+#[test]
+fn max_compressed_len_saturates() {
+    assert_eq!(usize::MAX, blosc_rs_max_compressed_len(usize::MAX));
+}
@@ -0,0 +1,33 @@
+// vim: tw=80
+//! Tests for `set_default_compressor`.
+//!
+//! The default compressor is a process-wide setting, so these tests get their
+//! own process, and run sequentially.
+
+use blosc::*;
+
+#[test]
+fn default_compressor() {
+    assert_eq!(Compressor::BloscLZ, Compressor::default());
+    assert_eq!(Context::new(), Context::default());
+
+    set_default_compressor(Compressor::Zstd).unwrap();
+    let ctx = Context::default();
+    assert_eq!(Context::new().compressor(Compressor::Zstd).unwrap(), ctx);
+    let data: Vec<u32> = (0..1000).collect();
+    let compressed = ctx.shuffle(ShuffleMode::Byte).compress(&data[..]);
+    let recovered = Context::from_buffer(&compressed).unwrap();
+    assert_eq!(
+        ctx.shuffle(ShuffleMode::Byte),
+        recovered.blocksize(None).typesize(None)
+    );
+    // Neither Context::new nor Compressor::default should be affected
+    assert_eq!(Compressor::BloscLZ, Compressor::default());
+    assert_ne!(Context::new(), Context::default());
+
+    assert_eq!(
+        Err(BloscError::CompressorNotSupported),
+        set_default_compressor(Compressor::Invalid)
+    );
+    assert_eq!(ctx, Context::default());
+}
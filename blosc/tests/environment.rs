@@ -0,0 +1,31 @@
+// vim: tw=80
+//! Tests that C-Blosc's environment variables don't override a `Context`.
+//!
+//! The environment is process-wide, so these tests get their own process.
+
+use blosc::*;
+use std::env;
+
+#[test]
+fn environment_is_ignored() {
+    let data: Vec<u32> = (0..65536).collect();
+    let ctx = Context::new()
+        .clevel(Clevel::L5)
+        .compressor(Compressor::LZ4)
+        .unwrap()
+        .shuffle(ShuffleMode::Byte);
+    let expected = ctx.compress(&data[..]);
+
+    env::set_var("BLOSC_CLEVEL", "9");
+    env::set_var("BLOSC_SHUFFLE", "BITSHUFFLE");
+    env::set_var("BLOSC_TYPESIZE", "1");
+    env::set_var("BLOSC_COMPRESSOR", "zstd");
+    env::set_var("BLOSC_BLOCKSIZE", "4096");
+    env::set_var("BLOSC_SPLITMODE", "NEVER");
+    let compressed = ctx.compress(&data[..]);
+
+    assert_eq!(expected.as_bytes(), compressed.as_bytes());
+    let recovered = Context::from_buffer(&compressed).unwrap();
+    assert_eq!(Context::from_buffer(&expected).unwrap(), recovered);
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
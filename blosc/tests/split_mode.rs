@@ -0,0 +1,72 @@
+// vim: tw=80
+//! Tests for `Context::split_mode` and `set_global_splitmode`.
+//!
+//! C-Blosc's split mode is a process-wide setting, so these tests get their
+//! own process, and run sequentially.
+
+use blosc::*;
+
+#[test]
+fn split_mode() {
+    let cases = [
+        (SplitMode::Always, 0),
+        (SplitMode::Never, 0x10),
+        (SplitMode::Auto, 0x10),
+        (SplitMode::ForwardCompat, 0x10),
+    ];
+    let data: Vec<u32> = (0..65536).collect();
+    for (split_mode, dont_split) in cases.iter() {
+        let ctx = Context::new()
+            .compressor(Compressor::Zstd)
+            .unwrap()
+            .shuffle(ShuffleMode::Byte)
+            .split_mode(*split_mode);
+        let compressed = ctx.compress(&data[..]);
+        // The header's flags byte records whether blocks were split
+        assert_eq!(
+            *dont_split,
+            compressed.as_ref()[2] & 0x10,
+            "{:?}",
+            split_mode
+        );
+        assert_eq!(data, decompress(&compressed).unwrap());
+    }
+
+    // A Context's own split mode shouldn't clobber the global one
+    let plain = Context::new().shuffle(ShuffleMode::Byte);
+    assert_eq!(0, plain.compress(&data[..]).as_ref()[2] & 0x10);
+    set_global_splitmode(SplitMode::Never);
+    assert_eq!(0x10, plain.compress(&data[..]).as_ref()[2] & 0x10);
+    let always = plain.split_mode(SplitMode::Always);
+    assert_eq!(0, always.compress(&data[..]).as_ref()[2] & 0x10);
+    assert_eq!(0x10, plain.compress(&data[..]).as_ref()[2] & 0x10);
+    set_global_splitmode(SplitMode::ForwardCompat);
+    assert_eq!(0, plain.compress(&data[..]).as_ref()[2] & 0x10);
+}
+
+// Contexts with different split modes shouldn't see each other's setting, even
+// when compressing concurrently.
+#[test]
+fn split_mode_concurrent() {
+    let data: Vec<u32> = (0..65536).collect();
+    let threads = [(SplitMode::Always, 0), (SplitMode::Never, 0x10)]
+        .iter()
+        .map(|&(split_mode, dont_split)| {
+            let data = data.clone();
+            std::thread::spawn(move || {
+                let ctx = Context::new()
+                    .compressor(Compressor::Zstd)
+                    .unwrap()
+                    .shuffle(ShuffleMode::Byte)
+                    .split_mode(split_mode);
+                for _ in 0..1000 {
+                    let compressed = ctx.compress(&data[..]);
+                    assert_eq!(dont_split, compressed.as_ref()[2] & 0x10);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
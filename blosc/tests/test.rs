@@ -4,9 +4,10 @@ use blosc::*;
 use rand::distributions::{Distribution, Uniform};
 use rstest::rstest;
 use std::{
-    collections::hash_map::DefaultHasher,
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeMap},
     hash::{Hash, Hasher},
-    mem,
+    io, mem, thread,
 };
 
 #[test]
@@ -14,6 +15,88 @@ fn test_invalid_compressor() {
     assert!(Context::new().compressor(Compressor::Invalid).is_err())
 }
 
+#[rstest]
+#[case::lz4(
+    Context::LZ4_SHUFFLE_L5,
+    Compressor::LZ4,
+    Clevel::L5,
+    ShuffleMode::Byte
+)]
+#[case::zstd(
+    Context::ZSTD_BITSHUFFLE_L9,
+    Compressor::Zstd,
+    Clevel::L9,
+    ShuffleMode::Bit
+)]
+fn context_preset(
+    #[case] preset: Context,
+    #[case] compressor: Compressor,
+    #[case] clevel: Clevel,
+    #[case] shuffle: ShuffleMode,
+) {
+    let ctx = Context::new()
+        .compressor(compressor)
+        .unwrap()
+        .clevel(clevel)
+        .shuffle(shuffle);
+    assert_eq!(ctx, preset);
+    let data: Vec<u32> = (0..1000).collect();
+    assert_eq!(data, decompress(&preset.compress(&data[..])).unwrap());
+}
+
+#[rstest]
+#[case::blosclz(Compressor::BloscLZ, 0)]
+#[case::lz4(Compressor::LZ4, 1)]
+#[case::lz4hc(Compressor::LZ4HC, 2)]
+#[case::snappy(Compressor::Snappy, 3)]
+#[case::zlib(Compressor::Zlib, 4)]
+#[case::zstd(Compressor::Zstd, 5)]
+fn compressor_id(#[case] compressor: Compressor, #[case] id: u8) {
+    assert_eq!(id, compressor.to_id());
+    assert_eq!(Some(compressor), Compressor::from_id(id));
+}
+
+#[test]
+fn compressor_from_invalid_id() {
+    assert_eq!(None, Compressor::from_id(6));
+    assert_eq!(None, Compressor::from_id(Compressor::Invalid.to_id()));
+}
+
+#[rstest]
+#[case::blosclz(0, "blosclz")]
+#[case::lz4(1, "lz4")]
+#[case::lz4hc(2, "lz4hc")]
+#[case::snappy(3, "snappy")]
+#[case::zlib(4, "zlib")]
+#[case::zstd(5, "zstd")]
+fn compcode_name(#[case] code: i32, #[case] name: &str) {
+    assert_eq!(Some(name), compcode_to_name(code));
+    assert_eq!(Some(code), name_to_compcode(name));
+}
+
+#[rstest]
+#[case::blosclz("blosclz", Ok(Compressor::BloscLZ))]
+#[case::lz4("lz4", Ok(Compressor::LZ4))]
+#[case::lz4hc("lz4hc", Ok(Compressor::LZ4HC))]
+#[case::snappy("snappy", Ok(Compressor::Snappy))]
+#[case::zlib("zlib", Ok(Compressor::Zlib))]
+#[case::zstd("zstd", Ok(Compressor::Zstd))]
+#[case::unknown("lzma", Err(BloscError::UnknownCompressor))]
+#[case::empty("", Err(BloscError::UnknownCompressor))]
+fn compressor_by_name(#[case] name: &str, #[case] expected: Result<Compressor>) {
+    assert_eq!(expected, name.parse::<Compressor>());
+    let expected_ctx = expected.and_then(|c| Context::new().compressor(c));
+    assert_eq!(expected_ctx, Context::new().compressor_by_name(name));
+}
+
+#[test]
+fn compcode_name_unknown() {
+    assert_eq!(None, compcode_to_name(-1));
+    assert_eq!(None, compcode_to_name(6));
+    assert_eq!(None, name_to_compcode("invalid"));
+    assert_eq!(None, name_to_compcode("lz4\0"));
+}
+
 #[rstest]
 #[case::baseline(None, None, Clevel::L2, Compressor::LZ4, ShuffleMode::Byte)]
 #[case::forced_blocksize(Some(65536), None, Clevel::L2, Compressor::LZ4, ShuffleMode::Byte)]
@@ -26,6 +109,8 @@ fn test_invalid_compressor() {
 #[case::zstd(None, None, Clevel::L2, Compressor::Zstd, ShuffleMode::Byte)]
 #[case::nosuffle(None, None, Clevel::L2, Compressor::LZ4, ShuffleMode::None)]
 #[case::bitshuffle(None, None, Clevel::L2, Compressor::LZ4, ShuffleMode::Bit)]
+#[case::autoshuffle(None, None, Clevel::L2, Compressor::LZ4, ShuffleMode::Auto)]
+#[case::autoshuffle_bytes(None, Some(1), Clevel::L2, Compressor::LZ4, ShuffleMode::Auto)]
 #[case::maxcompress(None, None, Clevel::L9, Compressor::Zstd, ShuffleMode::Bit)]
 #[case::forced_typesize(None, Some(2), Clevel::L2, Compressor::LZ4, ShuffleMode::Byte)]
 fn round_trip(
@@ -56,33 +141,2014 @@ fn round_trip(
     assert_eq!(sample, decoded);
 }
 
+#[cfg(feature = "testutil")]
+#[rstest]
+#[case::default(Context::new())]
+#[case::bitshuffle(Context::new().shuffle(ShuffleMode::Bit))]
+#[case::forced_typesize(Context::new().typesize(Some(3)))]
+#[case::checksum(Context::new().with_checksum(Checksum::Crc32c))]
+fn assert_round_trip(#[case] ctx: Context) {
+    let data: Vec<(u32, f32)> = (0..1000).map(|i| (i, i as f32 / 7.0)).collect();
+    blosc::assert_round_trip(&ctx, &data[..]);
+    blosc::assert_round_trip::<u8>(&ctx, &[]);
+}
+
 #[test]
-fn test_buffer_hash() {
-    let data: Vec<u8> = vec![1, 2, 3];
-    let ctx = Context::new();
+fn test_buffer_into_iter() {
+    let data: Vec<u32> = vec![1, 1, 2, 5, 8, 13, 21, 34, 55, 89, 144];
+    let compressed = Context::new().compress(&data[..]);
+    let mut borrowed = Vec::new();
+    for x in &compressed {
+        borrowed.push(x);
+    }
+    assert_eq!(data, borrowed);
+    let odd = compressed
+        .into_iter()
+        .filter(|x| x % 2 == 1)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![1, 1, 5, 13, 21, 55, 89], odd);
+}
+
+#[test]
+fn test_simd_info() {
+    let info = simd_info();
+    if cfg!(feature = "static") {
+        assert_eq!(Some(cfg!(target_feature = "sse2")), info.sse2_compiled);
+        assert_eq!(Some(cfg!(target_feature = "avx2")), info.avx2_compiled);
+    } else {
+        assert_eq!(None, info.sse2_compiled);
+        assert_eq!(None, info.avx2_compiled);
+    }
+    // Every x86_64 CPU has SSE2
+    if cfg!(target_arch = "x86_64") {
+        assert!(info.sse2_cpu);
+    }
+    // AVX2 implies SSE2
+    assert!(info.sse2_cpu || !info.avx2_cpu);
+}
+
+#[rstest]
+#[case::tiny(Clevel::L2, 1000)]
+#[case::l1(Clevel::L1, 1 << 20)]
+#[case::l2(Clevel::L2, 1 << 20)]
+#[case::l5(Clevel::L5, 1 << 20)]
+#[case::l9(Clevel::L9, 1 << 22)]
+#[case::store(Clevel::None, 1 << 20)]
+fn suggest_blocksize(#[case] clevel: Clevel, #[case] len: usize) {
+    let data = vec![0u32; len];
+    let compressed = Context::new().clevel(clevel).compress(&data[..]);
+    let expected = blosc::suggest_blocksize(clevel, mem::size_of::<u32>(), len * 4);
+    assert_eq!(compressed.blocksize(), expected);
+}
+
+#[rstest]
+#[case::automatic(None, 65536)]
+#[case::forced(Some(8192), 8192)]
+fn buffer_blocksize(#[case] blocksize: Option<usize>, #[case] expected: usize) {
+    let data = vec![0u8; 1 << 20];
+    let compressed = Context::new()
+        .blocksize(blocksize)
+        .compressor(Compressor::Zstd)
+        .unwrap()
+        .compress(&data[..]);
+    assert_eq!(expected, compressed.blocksize());
+}
+
+#[rstest]
+#[case::empty(vec![])]
+#[case::one_row(vec![vec![1.0, 2.0, 3.0]])]
+#[case::ragged(vec![vec![1.0], vec![], vec![2.0, 3.0, 4.0], vec![5.0, 6.0]])]
+fn ragged_round_trip(#[case] rows: Vec<Vec<f32>>) {
+    let slices = rows.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let compressed = ctx.compress_ragged(&slices[..]);
+    assert_eq!(rows, decompress_ragged(&compressed).unwrap());
+}
+
+#[test]
+fn ragged_zero_sized() {
+    let rows: [&[()]; 2] = [&[(), ()], &[]];
+    let compressed = Context::new().compress_ragged(&rows[..]);
+    assert_eq!(
+        Some(BloscError::TypesizeMismatch),
+        decompress_ragged(&compressed).err()
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Sample {
+    value: f64,
+    channel: u8,
+    flags: [u8; 2],
+}
+
+unsafe impl Columnar for Sample {
+    const FIELDS: &'static [(usize, usize)] = &[(0, 8), (8, 1), (9, 1), (10, 1)];
+}
+
+#[rstest]
+#[case::empty(0)]
+#[case::one(1)]
+#[case::many(10_000)]
+fn soa_round_trip(#[case] len: u32) {
+    let samples: Vec<Sample> = (0..len)
+        .map(|i| Sample {
+            value: f64::from(i) / 8.0,
+            channel: (i % 4) as u8,
+            flags: [(i % 3) as u8, 1],
+        })
+        .collect();
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let compressed = ctx.compress_soa(&samples[..]);
+    assert_eq!(samples, decompress_soa(&compressed).unwrap());
+    let blob: Vec<u8> = compressed.into();
+    let reader = ColumnReader::new(&blob[8..]).unwrap();
+    assert_eq!(4, reader.len());
+}
+
+#[rstest]
+#[case::compressible(Clevel::L2, false)]
+#[case::clevel_none(Clevel::None, true)]
+fn compress_with_stats(#[case] clevel: Clevel, #[case] memcpyed: bool) {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().clevel(clevel).shuffle(ShuffleMode::Byte);
+    let (compressed, stats) = ctx.compress_with_stats(&data[..]);
+    assert_eq!(4000, stats.uncompressed_bytes);
+    assert_eq!(compressed.size(), stats.compressed_bytes);
+    assert_eq!(4000.0 / compressed.size() as f64, stats.ratio);
+    assert_eq!(memcpyed, stats.memcpyed);
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn compress_instrumented() {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let (compressed, timing) = ctx.compress_instrumented(&data[..]);
+    assert_eq!(4000, timing.bytes_in);
+    assert_eq!(compressed.size(), timing.bytes_out);
+    assert_eq!(ctx.compress(&data[..]).as_bytes(), compressed.as_bytes());
+}
+
+#[rstest]
+#[case::blosclz(Compressor::BloscLZ, ShuffleMode::None, Compressor::BloscLZ)]
+#[case::lz4(Compressor::LZ4, ShuffleMode::Byte, Compressor::LZ4)]
+#[case::lz4hc(Compressor::LZ4HC, ShuffleMode::Byte, Compressor::LZ4)]
+#[case::zlib(Compressor::Zlib, ShuffleMode::Bit, Compressor::Zlib)]
+#[case::zstd(Compressor::Zstd, ShuffleMode::Bit, Compressor::Zstd)]
+fn context_from_buffer(
+    #[case] compressor: Compressor,
+    #[case] shuffle_mode: ShuffleMode,
+    #[case] expected_compressor: Compressor,
+) {
+    let data: Vec<u32> = (0..100_000).collect();
+    let ctx = Context::new()
+        .clevel(Clevel::L5)
+        .compressor(compressor)
+        .unwrap()
+        .shuffle(shuffle_mode)
+        .typesize(Some(2));
     let compressed = ctx.compress(&data[..]);
-    let mut buffer_hasher = DefaultHasher::new();
-    compressed.hash(&mut buffer_hasher);
-    let mut slice_hasher = DefaultHasher::new();
-    slice_hasher.write(compressed.as_ref());
-    assert_eq!(buffer_hasher.finish(), slice_hasher.finish());
+    let expected = Context::new()
+        .blocksize(Some(compressed.blocksize()))
+        .compressor(expected_compressor)
+        .unwrap()
+        .shuffle(shuffle_mode)
+        .typesize(Some(2));
+    assert_eq!(expected, Context::from_buffer(&compressed).unwrap());
 }
 
-#[cfg(test)]
-mod validate {
+/// C-Blosc rounds the blocksize down to a whole number of elements
+#[rstest]
+#[case::automatic(None)]
+#[case::explicit(Some(1000))]
+fn blocksize_typesize(#[case] blocksize: Option<usize>) {
+    let records: Vec<[u8; 12]> = (0..100_000u32)
+        .map(|i| {
+            let mut record = [0u8; 12];
+            record[..4].copy_from_slice(&i.to_le_bytes());
+            record
+        })
+        .collect();
+    let ctx = Context::new()
+        .blocksize(blocksize)
+        .shuffle(ShuffleMode::Byte);
+    let compressed = ctx.compress(&records[..]);
+    assert_eq!(0, compressed.blocksize() % 12);
+    if let Some(blocksize) = blocksize {
+        assert_eq!(blocksize / 12 * 12, compressed.blocksize());
+    }
+}
+
+#[rstest]
+#[case::blosclz(Compressor::BloscLZ, Compressor::BloscLZ)]
+#[case::lz4(Compressor::LZ4, Compressor::LZ4)]
+#[case::lz4hc(Compressor::LZ4HC, Compressor::LZ4)]
+#[case::zlib(Compressor::Zlib, Compressor::Zlib)]
+#[case::zstd(Compressor::Zstd, Compressor::Zstd)]
+fn effective_compressor(#[case] compressor: Compressor, #[case] expected: Compressor) {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().compressor(compressor).unwrap();
+    let mut compressed = ctx.compress(&data[..]);
+    assert_eq!(Ok(expected), compressed.effective_compressor());
+    compressed.clear();
+    assert_eq!(
+        Err(BloscError::ValidationError),
+        compressed.effective_compressor()
+    );
+}
+
+#[test]
+fn context_from_cleared_buffer() {
+    let mut compressed = Context::new().compress(&[0u32; 100][..]);
+    compressed.clear();
+    Context::from_buffer(&compressed).unwrap_err();
+}
+
+#[rstest]
+#[case::roomy(usize::MAX, Some(Clevel::None))]
+#[case::tight(2000, Some(Clevel::L1))]
+#[case::impossible(16, None)]
+fn compress_to_budget(#[case] max_bytes: usize, #[case] expected: Option<Clevel>) {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let compressed = ctx.compress_to_budget(&data[..], max_bytes);
+    let expected = expected.map(|clevel| ctx.clevel(clevel).compress(&data[..]));
+    assert_eq!(
+        expected.as_ref().map(Buffer::as_bytes),
+        compressed.as_ref().map(Buffer::as_bytes)
+    );
+}
+
+#[rstest]
+#[case::one(1000, 1, vec![1000])]
+#[case::even(1000, 4, vec![250; 4])]
+#[case::uneven(1000, 3, vec![334, 333, 333])]
+#[case::more_parts_than_elements(2, 3, vec![1, 1, 0])]
+#[case::empty(0, 2, vec![0, 0])]
+fn compress_into_parts(#[case] len: u32, #[case] parts: usize, #[case] expected_lens: Vec<usize>) {
+    let data: Vec<u32> = (0..len).collect();
+    let bufs = Context::new().compress_into_parts(&data[..], parts);
+    let lens = bufs
+        .iter()
+        .map(|buf| decompress(buf).unwrap().len())
+        .collect::<Vec<_>>();
+    assert_eq!(expected_lens, lens);
+    let refs = bufs.iter().collect::<Vec<_>>();
+    assert_eq!(data, decompress_concat(&refs[..]).unwrap());
+}
+
+#[rstest]
+#[case::zeros(vec![0u32; 10_000])]
+#[case::sequence((0..10_000).collect())]
+#[case::empty(Vec::new())]
+fn measure_ratio(#[case] data: Vec<u32>) {
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let (_, stats) = ctx.compress_with_stats(&data[..]);
+    assert_eq!(stats.ratio, ctx.measure_ratio(&data[..]));
+    // The second call reuses the scratch buffer
+    assert_eq!(stats.ratio, ctx.measure_ratio(&data[..]));
+}
+
+#[test]
+fn test_compress_assume_init() {
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let data: Vec<u64> = (0..10_000).map(|i| i * 3).collect();
+    let uninit: Vec<mem::MaybeUninit<u64>> =
+        data.iter().copied().map(mem::MaybeUninit::new).collect();
+    let compressed = unsafe { ctx.compress_assume_init(&uninit[..]) };
+    assert_eq!(ctx.compress(&data[..]).as_bytes(), compressed.as_bytes());
+}
+
+#[rstest]
+#[case::empty(0)]
+#[case::partial_byte(13)]
+#[case::whole_bytes(1024)]
+fn compress_bools(#[case] len: usize) {
+    let mask: Vec<bool> = (0..len).map(|i| i % 3 == 0).collect();
+    let compressed = Context::new().compress_bools(&mask[..]);
+    assert_eq!(mask, decompress_bools(&compressed).unwrap());
+}
+
+#[test]
+fn decompress_bools_invalid() {
+    let compressed = Context::new().compress(&[1u8, 2, 3][..]);
+    assert_eq!(
+        Err(BloscError::ValidationError),
+        decompress_bools(&compressed)
+    );
+    let mut packed = 100u64.to_le_bytes().to_vec();
+    packed.push(0xff);
+    let compressed = Context::new().compress(&packed[..]);
+    assert_eq!(
+        Err(BloscError::ValidationError),
+        decompress_bools(&compressed)
+    );
+}
+
+#[rstest]
+#[case::one(Some(1), None, 400_000)]
+#[case::even(Some(4), None, 100_000)]
+#[case::uneven(Some(3), None, 133_336)]
+#[case::zero(Some(0), None, 400_000)]
+#[case::many(Some(1000), None, 400)]
+#[case::clamped(Some(10_000_000), None, 128)]
+#[case::precedence(Some(4), Some(8192), 100_000)]
+#[case::unset(None, Some(8192), 8192)]
+fn target_blocks(
+    #[case] n: Option<usize>,
+    #[case] blocksize: Option<usize>,
+    #[case] expected: usize,
+) {
+    let data: Vec<u32> = (0..100_000).collect();
+    let ctx = Context::new()
+        .split_mode(SplitMode::Never)
+        .blocksize(blocksize)
+        .target_blocks(n);
+    let compressed = ctx.compress(&data[..]);
+    assert_eq!(expected, compressed.blocksize());
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[test]
+fn test_target_blocks_empty() {
+    let compressed = Context::new().target_blocks(Some(4)).compress::<u32>(&[]);
+    assert!(decompress(&compressed).unwrap().is_empty());
+}
+
+#[rstest]
+#[case::records(24, 6, true)]
+#[case::empty(0, 6, true)]
+#[case::partial(25, 6, false)]
+#[case::zero(24, 0, false)]
+#[case::oversized(600, 300, true)]
+fn compress_with_schema(#[case] len: usize, #[case] typesize: usize, #[case] ok: bool) {
+    let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    match ctx.compress_with_schema(&data[..], typesize) {
+        Ok(compressed) => {
+            assert!(ok);
+            assert_eq!(data, decompress(&compressed).unwrap());
+            let expected = ctx.typesize(Some(typesize)).compress(&data[..]);
+            assert_eq!(expected.as_bytes(), compressed.as_bytes());
+        }
+        Err(e) => {
+            assert!(!ok);
+            assert_eq!(BloscError::TypesizeMismatch, e);
+        }
+    }
+}
+
+#[rstest]
+#[case::plain(Context::new().shuffle(ShuffleMode::Byte))]
+#[case::checksum(Context::new().with_checksum(Checksum::Crc32c))]
+#[case::big_endian(Context::new().big_endian())]
+fn compress_into_slice(#[case] ctx: Context) {
+    let data: Vec<u32> = (0..1000).collect();
+    let max_len = ctx.max_compressed_len(mem::size_of_val(&data[..]));
+    let mut slab = vec![0xffu8; max_len + 10];
+    let len = ctx.compress_into_slice(&data[..], &mut slab[..]).unwrap();
+    assert_eq!(ctx.compress(&data[..]).as_bytes(), &slab[..len]);
+}
+
+#[test]
+fn test_compress_into_slice_too_small() {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    assert_eq!(4020, ctx.max_compressed_len(4000));
+    let mut slab = vec![0u8; 4019];
+    assert_eq!(
+        Err(BloscError::DestTooSmall),
+        ctx.compress_into_slice(&data[..], &mut slab[..])
+    );
+}
+
+/// Larger inputs than C-Blosc's limit are rejected before anything is written.
+/// The input is zero-initialized, so it's cheap to allocate.
+#[test]
+fn test_compress_into_slice_too_large() {
+    let data = vec![0u64; 1 << 28];
+    let mut slab = vec![0u8; 4096];
+    assert_eq!(
+        Err(BloscError::SizeLimitExceeded),
+        Context::new().compress_into_slice(&data[..], &mut slab[..])
+    );
+}
+
+#[test]
+#[should_panic(expected = "C-Blosc's limit")]
+fn test_compress_too_large() {
+    let data = vec![0u64; 1 << 28];
+    Context::new().compress(&data[..]);
+}
+
+#[test]
+#[should_panic(expected = "zero parts")]
+fn test_compress_into_zero_parts() {
+    Context::new().compress_into_parts(&[1u32, 2, 3][..], 0);
+}
+
+#[rstest]
+#[case::sufficient(Some(8192), false)]
+#[case::too_small(Some(64), true)]
+#[case::below_header(Some(1), true)]
+#[case::unset(None, true)]
+fn dest_reserve_hint(#[case] hint: Option<usize>, #[case] full: bool) {
+    let data: Vec<u32> = (0..100_000).collect();
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let expected = ctx.compress(&data[..]);
+    let compressed = ctx
+        .dest_reserve_hint(hint)
+        .shrink(false)
+        .compress(&data[..]);
+    assert_eq!(expected.as_bytes(), compressed.as_bytes());
+    let capacity = Vec::from(compressed).capacity();
+    assert_eq!(full, capacity >= 400_000, "capacity was {}", capacity);
+}
+
+#[test]
+fn test_compress_iter() {
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let data: Vec<u64> = (0..10_000).map(|i| i * 3).collect();
+    let compressed = ctx.compress_iter(data.iter().copied());
+    assert_eq!(ctx.compress(&data[..]).as_bytes(), compressed.as_bytes());
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[test]
+fn test_compress_or_store() {
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let compressible: Vec<u32> = (0..1000).collect();
+    let stored = ctx.compress_or_store(&compressible[..]);
+    assert!(matches!(stored, Stored::Compressed(_)));
+    assert!(stored.size() < 4000);
+    assert_eq!(compressible, decompress_stored(&stored).unwrap());
+
+    let mut rng = rand::thread_rng();
+    let dist = Uniform::new_inclusive(0, u64::MAX);
+    let random: Vec<u64> = dist.sample_iter(&mut rng).take(1000).collect();
+    let stored = ctx.compress_or_store(&random[..]);
+    assert!(matches!(stored, Stored::Raw(_)));
+    assert_eq!(8000, stored.size());
+    assert_eq!(random, decompress_stored(&stored).unwrap());
+}
+
+#[test]
+fn test_compress_raw() {
+    let data: Vec<u16> = (0..1000).collect();
+    let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    let (bytes, nelem) = ctx.compress_raw(&data[..]);
+    assert_eq!(1000, nelem);
+    assert_eq!(ctx.compress(&data[..]).as_bytes(), &bytes[..]);
+    assert_eq!(Ok(data), unsafe { decompress_bytes::<u16>(&bytes[..]) });
+}
+
+#[test]
+fn test_compress_reuse() {
+    let ctx = Context::new();
+    let mut buf = ctx.compress(&[0u32; 4096][..]);
+    buf.clear();
+    assert_eq!(0, buf.size());
+    decompress(&buf).unwrap_err();
+
+    let data: Vec<u32> = (0..4096).collect();
+    ctx.compress_reuse(&data[..], &mut buf);
+    assert_eq!(data, decompress(&buf).unwrap());
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn test_buffer_digest() {
+    use sha2::{Digest, Sha256};
+
+    let data: Vec<u8> = vec![1, 2, 3];
+    let compressed = Context::new().compress(&data[..]);
+    let expected = Sha256::digest(compressed.as_ref());
+    assert_eq!(expected, compressed.digest::<Sha256>());
+}
+
+#[test]
+fn test_buffer_len() {
+    let mut compressed = Context::new().compress(&[1u32, 2, 3][..]);
+    assert_eq!(compressed.size(), compressed.len());
+    assert_eq!(compressed.as_ref(), compressed.as_bytes());
+    assert!(!compressed.is_empty());
+    compressed.clear();
+    assert_eq!(0, compressed.len());
+    assert!(compressed.is_empty());
+}
+
+#[test]
+fn test_buffer_display() {
+    let ctx = Context::new()
+        .compressor(Compressor::Zstd)
+        .unwrap()
+        .shuffle(ShuffleMode::Bit);
+    let mut compressed = ctx.compress(&[0u64; 1000][..]);
+    assert_eq!(
+        format!(
+            "7.8 KiB → {} B ({:.1}x, zstd, bitshuffle)",
+            compressed.size(),
+            8000.0 / compressed.size() as f64
+        ),
+        compressed.to_string()
+    );
+    compressed.clear();
+    assert_eq!("empty", compressed.to_string());
+}
+
+mod columns {
     use super::*;
 
     #[test]
-    fn ok() {
-        let data: Vec<u16> = vec![1, 2, 3, 65535];
-        let ctx = Context::new();
+    fn round_trip() {
+        let ints: Vec<i64> = (0..1000).collect();
+        let floats: Vec<f32> = (0..500).map(|i| i as f32 / 2.0).collect();
+        let mut columns = ColumnSet::new();
+        columns.push(&Context::new().shuffle(ShuffleMode::Byte), &ints[..]);
+        columns.push(&Context::new().with_checksum(Checksum::Crc32c), &floats[..]);
+        columns.push::<u8>(&Context::new(), &[]);
+        let blob = columns.finish();
+
+        let reader = ColumnReader::new(&blob[..]).unwrap();
+        assert_eq!(3, reader.len());
+        assert_eq!(floats, unsafe { reader.decompress::<f32>(1) }.unwrap());
+        assert_eq!(ints, unsafe { reader.decompress::<i64>(0) }.unwrap());
+        assert!(unsafe { reader.decompress::<u8>(2) }.unwrap().is_empty());
+    }
+
+    #[test]
+    fn empty() {
+        let blob = ColumnSet::new().finish();
+        let reader = ColumnReader::new(&blob[..]).unwrap();
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn truncated() {
+        let mut columns = ColumnSet::new();
+        columns.push(&Context::new(), &[1u32, 2, 3][..]);
+        let blob = columns.finish();
+        let r = ColumnReader::new(&blob[..blob.len() - 1]);
+        assert_eq!(BloscError::ValidationError, r.unwrap_err());
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let mut columns = ColumnSet::new();
+        columns.push(&Context::new(), &[1u32, 2, 3][..]);
+        let blob = columns.finish();
+        let reader = ColumnReader::new(&blob[..]).unwrap();
+        let r = unsafe { reader.decompress::<u64>(0) };
+        assert_eq!(Err(BloscError::TypeMismatch), r);
+    }
+
+    #[test]
+    fn named() {
+        let ids: Vec<u32> = (0..1000).collect();
+        let temps: Vec<f64> = (0..1000).map(|i| i as f64 / 10.0).collect();
+        let mut columns = ColumnSet::new();
+        columns.push_named("id", &Context::new(), &ids[..]);
+        columns.push(&Context::new(), &[1u8, 2, 3][..]);
+        columns.push_named("temperature", &Context::new(), &temps[..]);
+        let blob = columns.finish();
+
+        let reader = ColumnReader::new(&blob[..]).unwrap();
+        assert_eq!(3, reader.len());
+        assert_eq!("id", reader.name(0));
+        assert_eq!("", reader.name(1));
+        assert_eq!(temps, reader.column::<f64>("temperature").unwrap());
+        assert_eq!(ids, reader.column::<u32>("id").unwrap());
+        assert_eq!(
+            vec![1u8, 2, 3],
+            unsafe { reader.decompress::<u8>(1) }.unwrap()
+        );
+        let r = reader.column::<u64>("id");
+        assert_eq!(Err(BloscError::TypeMismatch), r);
+        let r = reader.column::<u32>("pressure");
+        assert_eq!(Err(BloscError::ColumnNotFound), r);
+    }
+
+    #[test]
+    fn builder() {
+        let ints: Vec<i64> = (0..1000).collect();
+        let floats: Vec<f32> = (0..500).map(|i| i as f32 / 2.0).collect();
+        let mut builder = ColumnSetBuilder::new();
+        builder
+            .add_column("ints", &ints[..], &Context::new())
+            .add_column("floats", &floats[..], &Context::new().clevel(Clevel::L9))
+            .add_column::<[u8; 3]>("empty", &[], &Context::new());
+        let blob = builder.build();
+
+        let reader = ColumnReader::new(&blob[..]).unwrap();
+        assert_eq!(3, reader.len());
+        assert_eq!("floats", reader.name(1));
+        assert_eq!("f32", reader.type_name(1));
+        assert_eq!("[u8; 3]", reader.type_name(2));
+        assert_eq!(floats, reader.column::<f32>("floats").unwrap());
+        assert_eq!(ints, reader.column::<i64>("ints").unwrap());
+        assert!(reader.column::<[u8; 3]>("empty").unwrap().is_empty());
+    }
+
+    // Types of the same size are told apart by their type tags
+    #[test]
+    fn column_type_tag() {
+        let mut builder = ColumnSetBuilder::new();
+        builder.add_column("x", &[1u32, 2, 3][..], &Context::new());
+        let blob = builder.build();
+        let reader = ColumnReader::new(&blob[..]).unwrap();
+        assert_eq!(
+            Some(BloscError::TypeMismatch),
+            reader.column::<f32>("x").err()
+        );
+        assert_eq!(
+            Some(BloscError::TypeMismatch),
+            reader.column::<i32>("x").err()
+        );
+        assert_eq!(
+            Some(BloscError::TypeMismatch),
+            reader.column::<[u8; 4]>("x").err()
+        );
+        assert_eq!(vec![1u32, 2, 3], reader.column::<u32>("x").unwrap());
+    }
+
+    #[test]
+    fn invalid_name() {
+        let mut columns = ColumnSet::new();
+        columns.push_named("x", &Context::new(), &[1u32, 2, 3][..]);
+        let mut blob = columns.finish();
+        // The name follows the count and the single entry
+        blob[40] = 0xff;
+        let r = ColumnReader::new(&blob[..]);
+        assert_eq!(BloscError::ValidationError, r.unwrap_err());
+    }
+}
+
+mod frames {
+    use super::*;
+
+    fn log() -> Vec<u8> {
+        let ctx = Context::new().shuffle(ShuffleMode::Byte);
+        let mut log = Vec::new();
+        for i in 0..3u32 {
+            let data: Vec<u32> = (0..1000 * i).collect();
+            log.extend_from_slice(ctx.compress(&data[..]).as_ref());
+        }
+        log
+    }
+
+    #[test]
+    fn round_trip() {
+        let log = log();
+        let frames = unsafe { frames::<u32, _>(io::BufReader::new(&log[..])) };
+        let frames = frames.collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(3, frames.len());
+        for (i, frame) in frames.into_iter().enumerate() {
+            assert_eq!((0..1000 * i as u32).collect::<Vec<_>>(), frame);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let mut frames = unsafe { frames::<u32, _>(&[][..]) };
+        assert!(frames.next().is_none());
+    }
+
+    #[rstest]
+    #[case::header(4)]
+    #[case::body(100)]
+    fn truncated(#[case] cut: usize) {
+        let log = log();
+        let first = compressed_len(&log[..]).unwrap();
+        let mut frames = unsafe { frames::<u32, _>(&log[..first + cut]) };
+        assert!(frames.next().unwrap().unwrap().is_empty());
+        let e = frames.next().unwrap().unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, e.kind());
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut log = log();
+        // Claim a compressed length shorter than the header
+        log[12..16].copy_from_slice(&4u32.to_le_bytes());
+        let mut frames = unsafe { frames::<u32, _>(&log[..]) };
+        let e = frames.next().unwrap().unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, e.kind());
+        assert!(frames.next().is_none());
+    }
+}
+
+mod byte_order {
+    use super::*;
+
+    #[rstest]
+    #[case::big(ByteOrder::Big, Context::new().big_endian(), u64::to_be)]
+    #[case::little(ByteOrder::Little, Context::new().little_endian(), u64::to_le)]
+    fn round_trip(#[case] order: ByteOrder, #[case] ctx: Context, #[case] f: fn(u64) -> u64) {
+        let data: Vec<u64> = (0..10_000).map(|i| i * 3).collect();
+        let compressed = ctx.shuffle(ShuffleMode::Byte).compress(&data[..]);
+        let raw = unsafe { decompress_bytes::<u64>(compressed.as_ref()) }.unwrap();
+        assert_eq!(data.iter().copied().map(f).collect::<Vec<_>>(), raw);
+        let r = unsafe { decompress_with_byte_order(&compressed, order) };
+        assert_eq!(data, r.unwrap());
+    }
+
+    /// Elements are swapped according to the typesize, not the type.
+    #[test]
+    fn typesize() {
+        let data = [0x0102_0304u32, 0x0506_0708];
+        let ctx = Context::new().typesize(Some(2));
+        let native = ctx.compress(&data[..]);
+        let swapped = ctx.big_endian().compress(&data[..]);
+        let native = unsafe { decompress_bytes::<u8>(native.as_ref()) }.unwrap();
+        let swapped = unsafe { decompress_bytes::<u8>(swapped.as_ref()) }.unwrap();
+        let expected = native
+            .chunks(2)
+            .flat_map(|c| {
+                if cfg!(target_endian = "big") {
+                    [c[0], c[1]]
+                } else {
+                    [c[1], c[0]]
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, swapped);
+        let buf = ctx.big_endian().compress(&data[..]);
+        let r = unsafe { decompress_with_byte_order(&buf, ByteOrder::Big) };
+        assert_eq!(&data[..], &r.unwrap()[..]);
+    }
+}
+
+mod buffer_io {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u64> = (0..1000).collect();
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
         let compressed = ctx.compress(&data[..]);
-        assert_eq!(Ok(8), validate(compressed.as_ref()));
+        let mut stream = Vec::new();
+        compressed.write_to(&mut stream).unwrap();
+        assert_eq!(compressed.size() + 8, stream.len());
+        assert_eq!(&(compressed.size() as u64).to_le_bytes()[..], &stream[..8]);
+
+        let buf = unsafe { Buffer::<u64>::read_from(&mut &stream[..]) }.unwrap();
+        assert_eq!(compressed.as_bytes(), buf.as_bytes());
+        assert_eq!(data, decompress(&buf).unwrap());
     }
 
     #[test]
-    fn err() {
-        let compressed = vec![0u8; 8];
-        validate(compressed.as_ref()).unwrap_err();
+    fn truncated() {
+        let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+        let mut stream = Vec::new();
+        compressed.write_to(&mut stream).unwrap();
+        stream.pop();
+        let e = unsafe { Buffer::<u32>::read_from(&mut &stream[..]) }
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::UnexpectedEof, e.kind());
+    }
+
+    #[rstest]
+    #[case::raw(Framing::Raw, 0)]
+    #[case::u32(Framing::LengthPrefixedU32, 4)]
+    #[case::u64(Framing::LengthPrefixedU64, 8)]
+    fn framed(#[case] framing: Framing, #[case] prefix_len: usize) {
+        let data: Vec<u64> = (0..1000).collect();
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let compressed = ctx.compress(&data[..]);
+        let framed = compressed.to_framed(framing);
+        assert_eq!(compressed.size() + prefix_len, framed.len());
+        assert_eq!(compressed.as_bytes(), &framed[prefix_len..]);
+
+        let buf = unsafe { Buffer::<u64>::from_framed(&framed[..], framing) }.unwrap();
+        assert_eq!(data, decompress(&buf).unwrap());
+
+        let mut long = framed.clone();
+        long.push(0);
+        let r = unsafe { Buffer::<u64>::from_framed(&long[..], framing) };
+        // A raw frame has no length prefix, so it can't be too long
+        if framing != Framing::Raw {
+            assert_eq!(BloscError::ValidationError, r.err().unwrap());
+        }
+        // Cut into the Blosc frame, not just the checksum
+        let r = unsafe { Buffer::<u64>::from_framed(&framed[..framed.len() - 5], framing) };
+        if framing == Framing::Raw {
+            assert_eq!(BloscError::ValidationError, r.err().unwrap());
+        } else {
+            assert_eq!(BloscError::Truncated, r.err().unwrap());
+        }
+    }
+
+    #[test]
+    fn write_to_is_framed() {
+        let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+        let mut stream = Vec::new();
+        compressed.write_to(&mut stream).unwrap();
+        assert_eq!(compressed.to_framed(Framing::LengthPrefixedU64), stream);
+    }
+
+    #[test]
+    fn not_blosc() {
+        let mut stream = 4u64.to_le_bytes().to_vec();
+        stream.extend_from_slice(&[1, 2, 3, 4]);
+        let e = unsafe { Buffer::<u32>::read_from(&mut &stream[..]) }
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    }
+
+    #[test]
+    fn typesize_mismatch() {
+        let compressed = Context::new().compress(&[0u8; 6][..]);
+        let mut stream = Vec::new();
+        compressed.write_to(&mut stream).unwrap();
+        let e = unsafe { Buffer::<u32>::read_from(&mut &stream[..]) }
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    }
+}
+
+mod buffer_ref {
+    use super::*;
+
+    #[test]
+    fn as_ref_buffer() {
+        let data: Vec<u32> = (0..10_000).collect();
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let compressed = ctx.compress(&data[..]);
+        let borrowed = compressed.as_ref_buffer();
+        assert_eq!(compressed.as_bytes(), borrowed.as_bytes());
+        assert_eq!(compressed.blocksize(), borrowed.blocksize());
+        assert_eq!(compressed.flags(), borrowed.flags());
+        assert_eq!(compressed.validate(), borrowed.validate());
+        assert_eq!(data, borrowed.decompress().unwrap());
+        assert_eq!(&data[..5], &borrowed.decompress_prefix(5).unwrap()[..]);
+    }
+
+    #[test]
+    fn from_bytes() {
+        let data: Vec<u64> = (0..1000).collect();
+        let ctx = Context::new().compressor(Compressor::LZ4HC).unwrap();
+        let compressed = ctx.compress(&data[..]);
+        let bytes: Vec<u8> = compressed.into();
+        let borrowed = unsafe { BufferRef::<u64>::from_bytes(&bytes[..]) }.unwrap();
+        assert_eq!(bytes.len(), borrowed.size());
+        assert_eq!(Ok(Compressor::LZ4), borrowed.effective_compressor());
+        assert_eq!(data, borrowed.decompress().unwrap());
+    }
+
+    #[test]
+    fn not_blosc() {
+        let r = unsafe { BufferRef::<u32>::from_bytes(&[1, 2, 3, 4][..]) };
+        assert_eq!(BloscError::ValidationError, r.err().unwrap());
+    }
+
+    #[test]
+    fn truncated() {
+        let compressed = Context::new().compress(&[1u32, 2, 3][..]);
+        let bytes = &compressed.as_bytes()[..compressed.size() - 1];
+        let r = unsafe { BufferRef::<u32>::from_bytes(bytes) };
+        assert_eq!(BloscError::ValidationError, r.err().unwrap());
+    }
+
+    #[test]
+    fn typesize_mismatch() {
+        let compressed = Context::new().compress(&[0u8; 6][..]);
+        let r = unsafe { BufferRef::<u32>::from_bytes(compressed.as_bytes()) };
+        assert_eq!(BloscError::TypesizeMismatch, r.err().unwrap());
+    }
+}
+
+#[rstest]
+#[case::better(Context::ZSTD_BITSHUFFLE_L9, true)]
+#[case::worse(Context::new().clevel(Clevel::None), false)]
+fn recompress_if_better(#[case] ctx: Context, #[case] replaced: bool) {
+    let data: Vec<u32> = (0..10_000).collect();
+    let original = Context::new()
+        .shuffle(ShuffleMode::Byte)
+        .compress(&data[..]);
+    let kept = original.recompress_if_better(&ctx).unwrap();
+    assert_eq!(replaced, kept.as_bytes() != original.as_bytes());
+    assert_eq!(replaced, kept.size() < original.size());
+    assert_eq!(data, decompress(&kept).unwrap());
+}
+
+#[rstest]
+#[case::none(ShuffleMode::None, false, false)]
+#[case::byte(ShuffleMode::Byte, true, false)]
+#[case::bit(ShuffleMode::Bit, false, true)]
+fn buffer_with_shuffle(#[case] mode: ShuffleMode, #[case] byte: bool, #[case] bit: bool) {
+    let data: Vec<u64> = (0..10_000).collect();
+    let ctx = Context::new()
+        .compressor(Compressor::Zstd)
+        .unwrap()
+        .shuffle(ShuffleMode::Byte)
+        .with_checksum(Checksum::Crc32c);
+    let original = ctx.compress(&data[..]);
+    let reshuffled = original.with_shuffle(mode).unwrap();
+    let flags = reshuffled.flags().unwrap();
+    assert_eq!(byte, flags.byte_shuffle());
+    assert_eq!(bit, flags.bit_shuffle());
+    assert_eq!(Ok(Compressor::Zstd), reshuffled.effective_compressor());
+    assert_eq!(Ok(()), verify_checksum(&reshuffled));
+    assert_eq!(data, decompress(&reshuffled).unwrap());
+}
+
+#[test]
+fn test_buffer_map_type() {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().shuffle(ShuffleMode::Bit);
+    let compressed = ctx.compress(&data[..]);
+    let bytes = compressed.map_type::<[u8; 4]>().unwrap();
+    let expected: Vec<[u8; 4]> = data.iter().map(|x| x.to_ne_bytes()).collect();
+    assert_eq!(expected, decompress(&bytes).unwrap());
+    let halves = bytes.map_type::<u16>().unwrap();
+    assert_eq!(2000, decompress(&halves).unwrap().len());
+    let same = halves.map_type::<u32>().unwrap();
+    assert_eq!(data, decompress(&same).unwrap());
+}
+
+#[test]
+fn test_buffer_map_type_mismatch() {
+    let compressed = Context::new().compress(&[1u16, 2, 3][..]);
+    assert_eq!(
+        BloscError::TypesizeMismatch,
+        compressed.map_type::<u32>().err().unwrap()
+    );
+}
+
+#[test]
+fn test_buffer_map_type_zero_sized() {
+    let compressed = Context::new().compress(&[1u16, 2, 3][..]);
+    assert_eq!(
+        BloscError::TypesizeMismatch,
+        compressed.map_type::<[u8; 0]>().err().unwrap()
+    );
+}
+
+#[test]
+fn test_recompress_if_better_cleared() {
+    let mut buf = Context::new().compress(&[1u32, 2, 3][..]);
+    buf.clear();
+    let r = buf.recompress_if_better(&Context::new());
+    assert_eq!(Some(BloscError::ValidationError), r.err());
+}
+
+#[test]
+fn test_buffer_send_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let data = [1u32, 2, 3, 4];
+    let compressed = Context::new().compress(&data[..]);
+    assert_send_sync(&compressed);
+    assert_send_sync(&compressed.as_ref_buffer());
+    let decompressed = thread::spawn(move || decompress(&compressed).unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(&data[..], &decompressed[..]);
+}
+
+#[rstest]
+#[case::noshuffle(ShuffleMode::None, Clevel::L2, false, false, false)]
+#[case::byteshuffle(ShuffleMode::Byte, Clevel::L2, true, false, false)]
+#[case::bitshuffle(ShuffleMode::Bit, Clevel::L2, false, true, false)]
+#[case::memcpyed(ShuffleMode::Byte, Clevel::None, true, false, true)]
+fn buffer_flags(
+    #[case] shuffle_mode: ShuffleMode,
+    #[case] clevel: Clevel,
+    #[case] byte_shuffle: bool,
+    #[case] bit_shuffle: bool,
+    #[case] memcpyed: bool,
+) {
+    let data: Vec<u32> = (0..100_000).map(|i| i / 100).collect();
+    let ctx = Context::new().clevel(clevel).shuffle(shuffle_mode);
+    let mut compressed = ctx.compress(&data[..]);
+    let flags = compressed.flags().unwrap();
+    assert_eq!(Ok(flags), blosc::flags(compressed.as_ref()));
+    assert_eq!(byte_shuffle, flags.byte_shuffle());
+    assert_eq!(bit_shuffle, flags.bit_shuffle());
+    assert_eq!(memcpyed, flags.memcpyed());
+    // BloscLZ splits blocks by default
+    assert!(flags.split());
+    assert_eq!(compressed.as_ref()[2] & 0x1f, flags.bits());
+    compressed.clear();
+    assert_eq!(Err(BloscError::ValidationError), compressed.flags());
+}
+
+#[test]
+fn test_buffer_hash() {
+    let data: Vec<u8> = vec![1, 2, 3];
+    let ctx = Context::new();
+    let compressed = ctx.compress(&data[..]);
+    let mut buffer_hasher = DefaultHasher::new();
+    compressed.hash(&mut buffer_hasher);
+    let mut slice_hasher = DefaultHasher::new();
+    slice_hasher.write(compressed.as_ref());
+    assert_eq!(buffer_hasher.finish(), slice_hasher.finish());
+}
+
+#[test]
+fn test_buffer_from_slice() {
+    let data: Vec<u64> = (0..1000).collect();
+    let compressed = Buffer::from(&data[..]);
+    assert_eq!(
+        Context::new().compress(&data[..]).as_bytes(),
+        compressed.as_bytes()
+    );
+}
+
+#[test]
+fn test_buffer_ord() {
+    let ctx = Context::new();
+    let a = ctx.compress(&[1u32, 2, 3][..]);
+    let a2 = ctx.compress(&[1u32, 2, 3][..]);
+    let b = ctx.compress(&[4u32, 5, 6][..]);
+    assert_eq!(Ordering::Equal, a.cmp(&a2));
+    assert!(a == a2);
+    assert_eq!(a.as_bytes().cmp(b.as_bytes()), a.cmp(&b));
+    assert!(a != b);
+
+    let mut map = BTreeMap::new();
+    map.insert(a, "a");
+    map.insert(b, "b");
+    assert_eq!(Some(&"a"), map.get(&a2));
+}
+
+#[cfg(test)]
+mod archive {
+    use super::*;
+    use std::io::Cursor;
+
+    fn chunks() -> Vec<Vec<u32>> {
+        vec![(0..100).collect(), vec![], (1000..5000).collect()]
+    }
+
+    #[test]
+    fn round_trip() {
+        let ctx = Context::new().shuffle(ShuffleMode::Byte);
+        let mut writer = ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+        for chunk in chunks() {
+            writer.append(&ctx.compress(&chunk[..])).unwrap();
+        }
+        let file = writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::new(file).unwrap();
+        assert_eq!(3, reader.len());
+        assert_eq!(16000, reader.index()[2].uncompressed_len);
+        // Read out of order
+        for i in [2, 0, 1].iter() {
+            let chunk = unsafe { reader.decompress::<u32>(*i) }.unwrap();
+            assert_eq!(chunks()[*i], chunk);
+        }
+        reader.read_raw(3).unwrap_err();
+    }
+
+    #[test]
+    fn new_not_empty() {
+        let e = ArchiveWriter::new(Cursor::new(vec![0u8; 100]))
+            .err()
+            .unwrap();
+        assert_eq!(std::io::ErrorKind::InvalidInput, e.kind());
+    }
+
+    #[test]
+    fn reopen() {
+        let ctx = Context::new();
+        let mut writer = ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.append(&ctx.compress(&chunks()[0][..])).unwrap();
+        let file = writer.finish().unwrap();
+        let mut writer = ArchiveWriter::open(file).unwrap();
+        writer.append(&ctx.compress(&chunks()[2][..])).unwrap();
+        let file = writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::new(file).unwrap();
+        assert_eq!(2, reader.len());
+        assert_eq!(chunks()[0], unsafe { reader.decompress::<u32>(0) }.unwrap());
+        assert_eq!(chunks()[2], unsafe { reader.decompress::<u32>(1) }.unwrap());
+    }
+
+    #[test]
+    fn decompress_with_progress() {
+        let ctx = Context::new();
+        let mut writer = ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+        for chunk in chunks() {
+            writer.append(&ctx.compress(&chunk[..])).unwrap();
+        }
+        let mut reader = ArchiveReader::new(writer.finish().unwrap()).unwrap();
+
+        let mut reports = Vec::new();
+        let all = unsafe {
+            reader.decompress_with_progress::<u32, _>(|done, total| reports.push((done, total)))
+        };
+        assert_eq!(chunks().concat(), all.unwrap());
+        assert_eq!(vec![(1, 3), (2, 3), (3, 3)], reports);
+    }
+
+    #[test]
+    fn not_an_archive() {
+        let file = Cursor::new(vec![0u8; 64]);
+        ArchiveReader::new(file).err().unwrap();
+    }
+}
+
+mod checksum {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u32> = (0..1000).collect();
+        let plain = Context::new().compress(&data[..]);
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let compressed = ctx.compress(&data[..]);
+        assert_eq!(plain.size() + 4, compressed.size());
+        assert_eq!(Ok(()), verify_checksum(&compressed));
+        assert_eq!(data, decompress(&compressed).unwrap());
+        assert_eq!(
+            ctx,
+            Context::from_buffer(&compressed)
+                .unwrap()
+                .blocksize(None)
+                .typesize(None)
+        );
+    }
+
+    #[test]
+    fn cleared() {
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let mut compressed = ctx.compress(&[1u32, 2, 3][..]);
+        compressed.clear();
+        assert_eq!(
+            Err(BloscError::ValidationError),
+            verify_checksum(&compressed)
+        );
+    }
+
+    #[test]
+    fn reuse() {
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let mut compressed = ctx.compress(&[0u32; 1000][..]);
+        ctx.compress_reuse(&[1u32, 2, 3][..], &mut compressed);
+        assert_eq!(Ok(()), verify_checksum(&compressed));
+        assert_eq!(vec![1, 2, 3], decompress(&compressed).unwrap());
+    }
+
+    /// Plain Blosc readers can still decompress the frame
+    #[test]
+    fn compatibility() {
+        let data: Vec<u32> = (0..1000).collect();
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let compressed = ctx.compress(&data[..]);
+        let decompressed = unsafe { decompress_bytes::<u32>(compressed.as_ref()) };
+        assert_eq!(data, decompressed.unwrap());
+    }
+}
+
+#[cfg(test)]
+mod compressed_len {
+    use super::*;
+
+    #[test]
+    fn ok() {
+        let data: Vec<u32> = (0..1000).collect();
+        let compressed = Context::new().compress(&data[..]);
+        let mut stream: Vec<u8> = compressed.as_ref().to_vec();
+        stream.extend_from_slice(&[0xff; 32]);
+        assert_eq!(Ok(compressed.size()), compressed_len(&stream[..]));
+    }
+
+    #[test]
+    fn short() {
+        let data: Vec<u32> = (0..1000).collect();
+        let compressed = Context::new().compress(&data[..]);
+        let header = &compressed.as_ref()[..15];
+        compressed_len(header).unwrap_err();
+    }
+}
+
+#[test]
+fn test_decompress_boxed() {
+    let data: Vec<u32> = (0..1000).collect();
+    let compressed = Context::new().compress(&data[..]);
+    let decompressed = decompress_boxed(&compressed).unwrap();
+    assert_eq!(data.into_boxed_slice(), decompressed);
+}
+
+#[test]
+fn test_decompress_empty() {
+    let data: Vec<u32> = Vec::new();
+    let compressed = Context::new().compress(&data[..]);
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[test]
+fn test_decompress_forced_typesize() {
+    // The element count shouldn't depend on the typesize in the header
+    let data: Vec<[u8; 3]> = vec![[1, 2, 3], [4, 5, 6], [7, 8, 9]];
+    let compressed = Context::new()
+        .typesize(Some(2))
+        .shuffle(ShuffleMode::Byte)
+        .compress(&data[..]);
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[cfg(feature = "ndarray")]
+mod decompress_into_array_view {
+    use super::*;
+    use ndarray::{s, ShapeBuilder};
+
+    #[test]
+    fn c_order() {
+        let data: Vec<u32> = (0..24).collect();
+        let compressed = Context::new().compress(&data[..]);
+        let mut storage = [0u32; 30];
+        let mut view =
+            blosc::decompress_into_array_view(&compressed, &mut storage[..], (2, 3, 4)).unwrap();
+        assert_eq!(23, view[[1, 2, 3]]);
+        view[[0, 0, 0]] = 100;
+        assert_eq!(&data[1..], &storage[1..24]);
+        assert_eq!(100, storage[0]);
+        assert_eq!(&[0; 6], &storage[24..]);
+    }
+
+    #[test]
+    fn f_order() {
+        let data: Vec<u32> = (0..6).collect();
+        let compressed = Context::new().compress(&data[..]);
+        let mut storage = [0u32; 6];
+        let view =
+            blosc::decompress_into_array_view(&compressed, &mut storage[..], (2, 3).f()).unwrap();
+        assert_eq!(vec![0, 2, 4], view.slice(s![0, ..]).to_vec());
+    }
+
+    #[test]
+    fn short_storage() {
+        let compressed = Context::new().compress(&[0u32; 6][..]);
+        let mut storage = [0u32; 5];
+        let r = blosc::decompress_into_array_view(&compressed, &mut storage[..], 5);
+        assert_eq!(Err(BloscError::LengthMismatch), r.map(|_| ()));
+    }
+
+    #[test]
+    fn wrong_shape() {
+        let compressed = Context::new().compress(&[0u32; 6][..]);
+        let mut storage = [0u32; 6];
+        let r = blosc::decompress_into_array_view(&compressed, &mut storage[..], (2, 2));
+        assert_eq!(Err(BloscError::LengthMismatch), r.map(|_| ()));
+    }
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn test_decompress_to_bytes() {
+    use bytes::Buf;
+
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    let mut decompressed = decompress_to_bytes(&ctx.compress(&data[..])).unwrap();
+    assert_eq!(4000, decompressed.remaining());
+    for i in data {
+        assert_eq!(i, decompressed.get_u32_ne());
+    }
+}
+
+#[cfg(feature = "allocator-api2")]
+#[test]
+fn test_decompress_in() {
+    use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
+    use std::{cell::Cell, ptr::NonNull};
+
+    /// Counts its allocations
+    #[derive(Default)]
+    struct Counting(Cell<usize>);
+    unsafe impl Allocator for &Counting {
+        fn allocate(&self, layout: Layout) -> std::result::Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    let data: Vec<u32> = (0..1000).collect();
+    let compressed = Context::new().compress(&data[..]);
+    let alloc = Counting::default();
+    let decompressed = decompress_in(&compressed, &alloc).unwrap();
+    assert_eq!(&data[..], &decompressed[..]);
+    assert_eq!(1, alloc.0.get());
+}
+
+#[test]
+fn test_decompress_concat() {
+    let ctx = Context::new().shuffle(ShuffleMode::Byte);
+    let chunks: Vec<Vec<u32>> = vec![(0..1000).collect(), vec![], (1000..1500).collect()];
+    let bufs: Vec<Buffer<u32>> = chunks.iter().map(|c| ctx.compress(&c[..])).collect();
+    let refs: Vec<&Buffer<u32>> = bufs.iter().collect();
+    let expected: Vec<u32> = (0..1500).collect();
+    assert_eq!(expected, decompress_concat(&refs[..]).unwrap());
+    assert_eq!(Vec::<u32>::new(), decompress_concat::<u32>(&[]).unwrap());
+}
+
+#[test]
+fn test_decompress_concat_cleared() {
+    let ctx = Context::new();
+    let a = ctx.compress(&[1u32, 2, 3][..]);
+    let mut b = ctx.compress(&[4u32, 5][..]);
+    b.clear();
+    assert_eq!(
+        Err(BloscError::ValidationError),
+        decompress_concat(&[&a, &b])
+    );
+}
+
+#[rstest]
+#[case::empty("")]
+#[case::ascii("The quick brown fox jumps over the lazy dog")]
+#[case::multibyte("Ĉu vi parolas Esperanton? 日本語もあります")]
+fn decompress_string(#[case] text: &str) {
+    let text = text.repeat(100);
+    let compressed = Context::new().compress_str(&text);
+    assert_eq!(text, blosc::decompress_string(&compressed).unwrap());
+}
+
+#[test]
+fn test_decompress_string_invalid() {
+    let compressed = Context::new().compress(&[0x66u8, 0x6f, 0xff, 0x6f][..]);
+    assert_eq!(
+        Err(BloscError::InvalidUtf8),
+        blosc::decompress_string(&compressed)
+    );
+}
+
+#[rstest]
+#[case::exact(1000, Ok((0..1000).collect()))]
+#[case::short(999, Err(BloscError::LengthMismatch))]
+#[case::long(1001, Err(BloscError::LengthMismatch))]
+#[case::overflow(usize::MAX, Err(BloscError::LengthMismatch))]
+fn decompress_exact(#[case] expected_elems: usize, #[case] expected: Result<Vec<u32>>) {
+    let data: Vec<u32> = (0..1000).collect();
+    let compressed = Context::new().compress(&data[..]);
+    assert_eq!(
+        expected,
+        blosc::decompress_exact(&compressed, expected_elems)
+    );
+}
+
+#[rstest]
+#[case::under(1001, Ok((0..1000).collect()))]
+#[case::exact(1000, Ok((0..1000).collect()))]
+#[case::over(999, Err(BloscError::SizeLimitExceeded))]
+#[case::zero(0, Err(BloscError::SizeLimitExceeded))]
+#[case::unlimited(usize::MAX, Ok((0..1000).collect()))]
+fn decompress_capped(#[case] max_elems: usize, #[case] expected: Result<Vec<u32>>) {
+    let data: Vec<u32> = (0..1000).collect();
+    let compressed = Context::new().compress(&data[..]);
+    assert_eq!(expected, blosc::decompress_capped(&compressed, max_elems));
+}
+
+#[rstest]
+#[case::none(0, 0)]
+#[case::one(1, 1)]
+#[case::several_blocks(50_000, 50_000)]
+#[case::all(100_000, 100_000)]
+#[case::too_many(100_001, 100_000)]
+fn decompress_prefix(#[case] n: usize, #[case] expected_len: usize) {
+    let data: Vec<u32> = (0..100_000).collect();
+    let ctx = Context::new()
+        .blocksize(Some(4096))
+        .shuffle(ShuffleMode::Byte);
+    let compressed = ctx.compress(&data[..]);
+    let prefix = blosc::decompress_prefix(&compressed, n).unwrap();
+    assert_eq!(&data[..expected_len], &prefix[..]);
+}
+
+#[test]
+fn test_decompress_prefix_forced_typesize() {
+    let data: Vec<[u8; 3]> = (0..100).map(|i| [i, i + 1, i + 2]).collect();
+    let compressed = Context::new()
+        .typesize(Some(2))
+        .shuffle(ShuffleMode::Byte)
+        .compress(&data[..]);
+    assert_eq!(
+        &data[..3],
+        &blosc::decompress_prefix(&compressed, 3).unwrap()[..]
+    );
+    assert_eq!(
+        &data[..4],
+        &blosc::decompress_prefix(&compressed, 4).unwrap()[..]
+    );
+}
+
+#[test]
+fn test_decompress_prefix_cleared() {
+    let mut compressed = Context::new().compress(&[1u32, 2, 3][..]);
+    compressed.clear();
+    assert_eq!(
+        Err(BloscError::ValidationError),
+        blosc::decompress_prefix(&compressed, 1)
+    );
+}
+
+#[cfg(test)]
+mod decompress_errors {
+    use super::*;
+
+    fn compressed() -> Vec<u8> {
+        let data: Vec<u32> = (0..10_000).collect();
+        let ctx = Context::new().shuffle(ShuffleMode::Byte);
+        ctx.compress(&data[..]).into()
+    }
+
+    #[test]
+    fn corrupt() {
+        let mut compressed = compressed();
+        // Point the first block past the end of the buffer
+        compressed[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        let r = unsafe { decompress_bytes::<u32>(&compressed[..]) };
+        assert_eq!(Err(BloscError::CorruptData), r);
+    }
+
+    #[test]
+    fn compressor_not_supported() {
+        let mut compressed = compressed();
+        // Use a compression library code that C-Blosc doesn't know
+        compressed[2] |= 0xe0;
+        let r = unsafe { decompress_bytes::<u32>(&compressed[..]) };
+        assert_eq!(Err(BloscError::CompressorNotSupported), r);
+    }
+
+    #[test]
+    fn compressor_unavailable() {
+        // Every compressor is enabled in this build, so just check the message
+        let e = BloscError::CompressorUnavailable(Compressor::Zstd);
+        assert_eq!(
+            "Buffer was compressed with zstd, but c-Blosc was built without it",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn typesize_mismatch() {
+        let compressed = Context::new().compress(&[0u8; 6][..]);
+        let r = unsafe { decompress_bytes::<u32>(compressed.as_ref()) };
+        assert_eq!(Err(BloscError::TypesizeMismatch), r);
+    }
+
+    #[test]
+    fn truncated() {
+        let compressed = compressed();
+        let partial = &compressed[..compressed.len() - 1];
+        let r = unsafe { decompress_bytes::<u32>(partial) };
+        assert_eq!(Err(BloscError::Truncated), r);
+    }
+
+    #[test]
+    fn verified() {
+        let compressed = compressed();
+        let r = unsafe { decompress_bytes_verified::<u32>(&compressed[..]) };
+        assert_eq!((0..10_000).collect::<Vec<u32>>(), r.unwrap());
+    }
+
+    #[test]
+    fn verified_bad_block_offset() {
+        let mut compressed = compressed();
+        // Point the first block into the header
+        compressed[16..20].copy_from_slice(&0u32.to_le_bytes());
+        let r = unsafe { decompress_bytes_verified::<u32>(&compressed[..]) };
+        assert_eq!(Err(BloscError::CorruptData), r);
+    }
+
+    #[test]
+    fn verified_bad_header() {
+        let mut compressed = compressed();
+        // Set the reserved flag, which C-Blosc rejects
+        compressed[2] |= 0x08;
+        let r = unsafe { decompress_bytes_verified::<u32>(&compressed[..]) };
+        assert_eq!(Err(BloscError::ValidationError), r);
+    }
+
+    #[test]
+    fn verified_truncated() {
+        let compressed = compressed();
+        let partial = &compressed[..compressed.len() - 1];
+        let r = unsafe { decompress_bytes_verified::<u32>(partial) };
+        assert_eq!(Err(BloscError::Truncated), r);
+    }
+}
+
+/// Decompression sizes its output exactly, so there should be no need to shrink
+/// it.
+#[test]
+fn test_decompress_capacity() {
+    let data: Vec<u16> = (0..1001).collect();
+    let compressed = Context::new().typesize(Some(4)).compress(&data[..]);
+    let decompressed = decompress(&compressed).unwrap();
+    assert_eq!(decompressed.len(), decompressed.capacity());
+    let decompressed = unsafe { decompress_bytes::<u16>(compressed.as_ref()) }.unwrap();
+    assert_eq!(decompressed.len(), decompressed.capacity());
+}
+
+#[test]
+fn test_decompress_reuse() {
+    let ctx = Context::new().with_checksum(Checksum::Crc32c);
+    let big: Vec<u64> = (0..10_000).collect();
+    let mut dest = Vec::new();
+    decompress_reuse(&ctx.compress(&big[..]), &mut dest).unwrap();
+    assert_eq!(big, dest);
+    let capacity = dest.capacity();
+    decompress_reuse(&ctx.compress(&[1u64, 2, 3][..]), &mut dest).unwrap();
+    assert_eq!(vec![1, 2, 3], dest);
+    assert_eq!(capacity, dest.capacity());
+
+    let mut corrupt = ctx.compress(&[1u64, 2, 3][..]);
+    corrupt.clear();
+    assert!(decompress_reuse(&corrupt, &mut dest).is_err());
+    assert!(dest.is_empty());
+}
+
+#[test]
+fn test_decompress_bytes_with_len() {
+    let data: Vec<u64> = (0..1000).collect();
+    let compressed = Context::new().compress(&data[..]);
+    let (bytes, typesize) =
+        unsafe { decompress_bytes_with_len::<u8>(compressed.as_ref()) }.unwrap();
+    assert_eq!(8, typesize);
+    assert_eq!(data.len(), bytes.len() / typesize);
+    let (words, typesize) =
+        unsafe { decompress_bytes_with_len::<u16>(compressed.as_ref()) }.unwrap();
+    assert_eq!(8, typesize);
+    assert_eq!(4000, words.len());
+}
+
+#[test]
+fn test_decompress_bytes_short() {
+    let r = unsafe { decompress_bytes::<u8>(&[2, 1, 19, 4][..]) };
+    assert_eq!(Err(BloscError::ValidationError), r);
+}
+
+#[cfg(test)]
+mod decompress_bytes_limited {
+    use super::*;
+
+    #[test]
+    fn ok() {
+        let data: Vec<u32> = vec![1, 2, 3, 4];
+        let compressed = Context::new().compress(&data[..]);
+        let r = unsafe { decompress_bytes_limited::<u32>(compressed.as_ref(), 16) };
+        assert_eq!(Ok(data), r);
+    }
+
+    #[test]
+    fn too_large() {
+        let data: Vec<u32> = vec![1, 2, 3, 4];
+        let compressed = Context::new().compress(&data[..]);
+        let r = unsafe { decompress_bytes_limited::<u32>(compressed.as_ref(), 15) };
+        assert_eq!(Err(BloscError::SizeLimitExceeded), r);
+    }
+}
+
+#[rstest]
+#[case::exact(1000, Ok(()))]
+#[case::empty(0, Err(BloscError::LengthMismatch))]
+#[case::too_few(999, Err(BloscError::LengthMismatch))]
+#[case::too_many(1001, Err(BloscError::LengthMismatch))]
+#[case::huge(usize::MAX / 16, Err(BloscError::LengthMismatch))]
+#[case::overflow(usize::MAX / 4, Err(BloscError::LengthMismatch))]
+fn decompress_bytes_unchecked(#[case] nelem: usize, #[case] expected: Result<()>) {
+    let data: Vec<u64> = (0..1000).collect();
+    let compressed = Context::new().compress(&data[..]);
+    let r = unsafe { blosc::decompress_bytes_unchecked::<u64>(compressed.as_ref(), nelem) };
+    assert_eq!(expected.map(|_| data), r);
+}
+
+#[test]
+fn test_decompress_bytes_unchecked_short() {
+    let r = unsafe { blosc::decompress_bytes_unchecked::<u8>(&[2, 1, 19, 4][..], 0) };
+    assert_eq!(Err(BloscError::ValidationError), r);
+}
+
+#[rstest]
+#[case::noshuffle(ShuffleMode::None, None, false)]
+#[case::byteshuffle(ShuffleMode::Byte, None, false)]
+#[case::bitshuffle(ShuffleMode::Bit, None, true)]
+#[case::forced_typesize(ShuffleMode::Bit, Some(2), false)]
+#[case::forced_bad_typesize(ShuffleMode::Bit, Some(6), true)]
+fn context_validate(
+    #[case] shuffle_mode: ShuffleMode,
+    #[case] typesize: Option<usize>,
+    #[case] warn: bool,
+) {
+    let ctx = Context::new().shuffle(shuffle_mode).typesize(typesize);
+    let warnings = ctx.validate::<[u16; 3]>();
+    if warn {
+        let typesize = typesize.unwrap_or(6);
+        assert_eq!(vec![Warning::BitshuffleTypesize(typesize)], warnings);
+    } else {
+        assert!(warnings.is_empty());
+    }
+}
+
+#[rstest]
+#[case::divides(2, 6, false)]
+#[case::whole(6, 6, false)]
+#[case::doesnt_divide(4, 6, true)]
+#[case::bytes(2, 1, false)]
+fn context_validate_typesize(
+    #[case] typesize: usize,
+    #[case] elem_size: usize,
+    #[case] warn: bool,
+) {
+    let ctx = Context::new()
+        .shuffle(ShuffleMode::Byte)
+        .typesize(Some(typesize));
+    let warnings = match elem_size {
+        1 => ctx.validate::<u8>(),
+        6 => ctx.validate::<[u16; 3]>(),
+        _ => unreachable!(),
+    };
+    if warn {
+        assert_eq!(
+            vec![Warning::TypesizeMismatch(typesize, elem_size)],
+            warnings
+        );
+    } else {
+        assert!(warnings.is_empty());
+    }
+}
+
+#[rstest]
+#[case::disabled(None, 4, true)]
+#[case::small(Some(64), 15, false)]
+#[case::at_threshold(Some(64), 16, true)]
+#[case::large(Some(64), 1000, true)]
+fn context_auto_small(#[case] threshold: Option<usize>, #[case] len: u32, #[case] shuffled: bool) {
+    let data: Vec<u32> = (0..len).collect();
+    let ctx = Context::new()
+        .shuffle(ShuffleMode::Bit)
+        .auto_small(threshold);
+    let compressed = ctx.compress(&data[..]);
+    assert_eq!(shuffled, compressed.flags().unwrap().bit_shuffle());
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[rstest]
+#[case::byte(ShuffleMode::Byte)]
+#[case::bit(ShuffleMode::Bit)]
+#[case::auto(ShuffleMode::Auto)]
+fn context_no_filter(#[case] shuffle_mode: ShuffleMode) {
+    let data: Vec<u32> = (0..1000).collect();
+    let ctx = Context::new()
+        .shuffle(shuffle_mode)
+        .auto_small(Some(64))
+        .big_endian()
+        .no_filter();
+    assert_eq!(Context::new(), ctx);
+    let compressed = ctx.compress(&data[..]);
+    let flags = compressed.flags().unwrap();
+    assert!(!flags.byte_shuffle());
+    assert!(!flags.bit_shuffle());
+    assert_eq!(data, decompress(&compressed).unwrap());
+}
+
+#[rstest]
+#[case::snappy(Compressor::Snappy, Clevel::L5, true)]
+#[case::snappy_none(Compressor::Snappy, Clevel::None, false)]
+#[case::lz4(Compressor::LZ4, Clevel::L5, false)]
+#[case::zstd(Compressor::Zstd, Clevel::L9, false)]
+fn context_validate_clevel(
+    #[case] compressor: Compressor,
+    #[case] clevel: Clevel,
+    #[case] warn: bool,
+) {
+    let ctx = Context::new()
+        .compressor(compressor)
+        .unwrap()
+        .clevel(clevel);
+    let warnings = ctx.validate::<u32>();
+    if warn {
+        assert_eq!(vec![Warning::ClevelIgnored(compressor)], warnings);
+        assert_eq!(
+            "compression level is ignored by snappy",
+            warnings[0].to_string()
+        );
+    } else {
+        assert!(warnings.is_empty());
+    }
+}
+
+#[rstest]
+#[case::default(Context::new(), vec![])]
+#[case::shuffled(Context::new().typesize(Some(8)).shuffle(ShuffleMode::Byte), vec![])]
+#[case::no_shuffle(Context::new().typesize(Some(8)), vec![Warning::NoShuffle(8)])]
+#[case::no_shuffle_bytes(Context::new().typesize(Some(1)), vec![])]
+#[case::no_shuffle_odd(Context::new().typesize(Some(6)), vec![])]
+#[case::bitshuffle(
+    Context::new().typesize(Some(6)).shuffle(ShuffleMode::Bit),
+    vec![Warning::BitshuffleTypesize(6)]
+)]
+#[case::small_blocksize(
+    Context::new().typesize(Some(200)).shuffle(ShuffleMode::Byte).blocksize(Some(64)),
+    vec![Warning::BlocksizeTooSmall(64, 200)]
+)]
+#[case::clamped_blocksize(
+    Context::new().typesize(Some(100)).shuffle(ShuffleMode::Byte).blocksize(Some(64)),
+    vec![]
+)]
+#[case::snappy(
+    Context::new().compressor(Compressor::Snappy).unwrap(),
+    vec![Warning::ClevelIgnored(Compressor::Snappy)]
+)]
+#[case::too_large(
+    Context::new().typesize(Some(256)).shuffle(ShuffleMode::Bit),
+    vec![Warning::TypesizeTooLarge(256)]
+)]
+#[case::too_large_unshuffled(Context::new().typesize(Some(256)), vec![])]
+fn context_lint(#[case] ctx: Context, #[case] expected: Vec<Warning>) {
+    assert_eq!(expected, ctx.lint());
+}
+
+#[rstest]
+#[case::info(Warning::NoShuffle(8), Severity::Info)]
+#[case::preshuffled(Warning::LikelyPreshuffled(4), Severity::Info)]
+#[case::warning(Warning::BlocksizeTooSmall(64, 200), Severity::Warning)]
+fn warning_severity(#[case] warning: Warning, #[case] severity: Severity) {
+    assert_eq!(severity, warning.severity());
+}
+
+mod lint_data {
+    use super::*;
+
+    /// Transpose `bytes` into byte planes, like a byte shuffle would.
+    fn planes(bytes: &[u8], typesize: usize) -> Vec<u8> {
+        (0..typesize)
+            .flat_map(|j| bytes.iter().skip(j).step_by(typesize).copied())
+            .collect()
+    }
+
+    fn u32s() -> Vec<u8> {
+        (0..10_000u32).flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    fn f64s() -> Vec<u8> {
+        (0..10_000)
+            .flat_map(|i| (f64::from(i) / 100.0).sin().to_le_bytes())
+            .collect()
+    }
+
+    #[rstest]
+    #[case::u32s(u32s(), 4, false)]
+    #[case::shuffled_u32s(planes(&u32s(), 4), 4, true)]
+    #[case::f64s(f64s(), 8, false)]
+    #[case::shuffled_f64s(planes(&f64s(), 8), 8, true)]
+    #[case::random((0..40_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect(), 4, false)]
+    #[case::constant(vec![7u8; 40_000], 4, false)]
+    #[case::too_short(planes(&u32s()[..128], 4), 4, false)]
+    fn preshuffled(#[case] bytes: Vec<u8>, #[case] typesize: usize, #[case] flagged: bool) {
+        let ctx = Context::new()
+            .typesize(Some(typesize))
+            .shuffle(ShuffleMode::Byte);
+        let expected = if flagged {
+            vec![Warning::LikelyPreshuffled(typesize)]
+        } else {
+            vec![]
+        };
+        assert_eq!(expected, ctx.lint_data(&bytes[..]));
+    }
+
+    #[test]
+    fn no_shuffle() {
+        let ctx = Context::new().typesize(Some(4));
+        assert!(ctx.lint_data(&planes(&u32s(), 4)[..]).is_empty());
+    }
+
+    #[test]
+    fn auto_small() {
+        let ctx = Context::new()
+            .typesize(Some(4))
+            .shuffle(ShuffleMode::Byte)
+            .auto_small(Some(1 << 20));
+        assert!(ctx.lint_data(&planes(&u32s(), 4)[..]).is_empty());
+    }
+
+    #[test]
+    fn typed() {
+        let ctx = Context::new().shuffle(ShuffleMode::Bit);
+        let data: Vec<u32> = (0..10_000).collect();
+        assert!(ctx.lint_data(&data[..]).is_empty());
+        assert_eq!(
+            vec![Warning::BitshuffleTypesize(3)],
+            ctx.lint_data(&[[0u8; 3]; 10][..])
+        );
+    }
+}
+
+#[rstest]
+#[case::max(ShuffleMode::Byte, 255, false)]
+#[case::too_large(ShuffleMode::Byte, 256, true)]
+#[case::too_large_unshuffled(ShuffleMode::None, 256, false)]
+fn context_validate_typesize_too_large(
+    #[case] shuffle_mode: ShuffleMode,
+    #[case] typesize: usize,
+    #[case] warn: bool,
+) {
+    let ctx = Context::new()
+        .shuffle(shuffle_mode)
+        .typesize(Some(typesize));
+    let warnings = ctx.validate::<u8>();
+    if warn {
+        assert_eq!(vec![Warning::TypesizeTooLarge(typesize)], warnings);
+        assert_eq!(
+            "typesize of 256 is too large to shuffle; the maximum is 255",
+            warnings[0].to_string()
+        );
+    } else {
+        assert!(warnings.is_empty());
+    }
+}
+
+#[test]
+fn test_context_typesize_of_slice() {
+    let sample: [[u16; 3]; 0] = [];
+    let ctx = Context::new()
+        .shuffle(ShuffleMode::Byte)
+        .typesize_of_slice(&sample[..]);
+    let expected = Context::for_elements::<[u16; 3]>().shuffle(ShuffleMode::Byte);
+    assert_eq!(expected, ctx);
+}
+
+/// The fingerprint must never change, because it may be stored on disk.
+#[test]
+fn test_context_fingerprint_stable() {
+    assert_eq!(0x74c8_2ed3_0c1f_bbe7, Context::new().fingerprint());
+    let ctx = Context::new()
+        .clevel(Clevel::L9)
+        .compressor(Compressor::Zstd)
+        .unwrap()
+        .shuffle(ShuffleMode::Bit)
+        .typesize(Some(4))
+        .split_mode(SplitMode::Never)
+        .big_endian()
+        .with_checksum(Checksum::Crc32c);
+    assert_eq!(0xb931_2977_1a25_532d, ctx.fingerprint());
+}
+
+#[rstest]
+#[case::clevel(Context::new().clevel(Clevel::L9))]
+#[case::compressor(Context::new().compressor(Compressor::Zstd).unwrap())]
+#[case::shuffle(Context::new().shuffle(ShuffleMode::Bit))]
+#[case::typesize(Context::new().typesize(Some(4)))]
+#[case::blocksize(Context::new().blocksize(Some(4096)))]
+#[case::target_blocks(Context::new().target_blocks(Some(4)))]
+#[case::split_mode(Context::new().split_mode(SplitMode::Never))]
+#[case::byte_order(Context::new().big_endian())]
+#[case::checksum(Context::new().with_checksum(Checksum::Crc32c))]
+#[case::auto_small(Context::new().auto_small(Some(4096)))]
+fn context_fingerprint(#[case] ctx: Context) {
+    assert_ne!(Context::new().fingerprint(), ctx.fingerprint());
+    assert_eq!(ctx.fingerprint(), ctx.fingerprint());
+}
+
+#[test]
+fn test_context_fingerprint_memory_settings() {
+    let ctx = Context::new().shrink(false).dest_reserve_hint(Some(4096));
+    assert_eq!(Context::new().fingerprint(), ctx.fingerprint());
+}
+
+#[test]
+fn test_context_for_elements() {
+    let ctx = Context::for_elements::<[u16; 3]>();
+    assert_eq!(Context::new().typesize(Some(6)), ctx);
+    assert!(ctx.validate::<[u16; 3]>().is_empty());
+}
+
+#[cfg(test)]
+mod is_blosc {
+    use super::*;
+
+    #[rstest]
+    #[case::empty(0)]
+    #[case::memcpyed(3)]
+    #[case::compressed(100_000)]
+    fn ok(#[case] len: u32) {
+        let data: Vec<u32> = (0..len).collect();
+        let ctx = Context::new().shuffle(ShuffleMode::Byte);
+        let compressed = ctx.compress(&data[..]);
+        assert!(is_blosc(compressed.as_ref()));
+        let mut stream = compressed.as_bytes().to_vec();
+        stream.extend_from_slice(b"trailing garbage");
+        assert!(is_blosc(&stream[..]));
+        assert!(!is_blosc(&compressed.as_bytes()[..compressed.size() - 1]));
+    }
+
+    #[rstest]
+    #[case::version(0, 3)]
+    #[case::reserved_flag(2, 0x08)]
+    #[case::compressor(2, 0xe0)]
+    #[case::typesize(3, 0)]
+    #[case::blocksize(8, 0)]
+    fn corrupt(#[case] offset: usize, #[case] value: u8) {
+        let data: Vec<u32> = (0..1000).collect();
+        let mut compressed = Context::new().compress(&data[..]).as_bytes().to_vec();
+        if offset == 8 {
+            compressed[8..12].copy_from_slice(&[value; 4]);
+        } else if offset == 2 {
+            compressed[2] |= value;
+        } else {
+            compressed[offset] = value;
+        }
+        assert!(!is_blosc(&compressed[..]));
+    }
+
+    /// Arbitrary input must never cause a panic
+    #[test]
+    fn arbitrary() {
+        let mut rng = rand::thread_rng();
+        let dist = Uniform::new_inclusive(0u8, 255);
+        for len in 0..64 {
+            let bytes: Vec<u8> = (0..len).map(|_| dist.sample(&mut rng)).collect();
+            is_blosc(&bytes[..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod validate {
+    use super::*;
+
+    #[test]
+    fn ok() {
+        let data: Vec<u16> = vec![1, 2, 3, 65535];
+        let ctx = Context::new();
+        let compressed = ctx.compress(&data[..]);
+        assert_eq!(Ok(8), validate(compressed.as_ref()));
+    }
+
+    #[test]
+    fn err() {
+        let compressed = vec![0u8; 8];
+        validate(compressed.as_ref()).unwrap_err();
+    }
+
+    #[test]
+    fn buffer() {
+        let data: Vec<u16> = vec![1, 2, 3, 65535];
+        let ctx = Context::new().with_checksum(Checksum::Crc32c);
+        let mut compressed = ctx.compress(&data[..]);
+        assert_eq!(Ok(8), compressed.validate());
+        compressed.clear();
+        assert_eq!(Err(BloscError::ValidationError), compressed.validate());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod tagged {
+    use super::*;
+
+    fn round_trip<T, U>(data: &[T]) -> Result<Vec<U>>
+    where
+        T: Copy,
+        U: Copy,
+    {
+        let tagged = TaggedBuffer::from(Context::new().compress(data));
+        let serialized = bincode::serialize(&tagged).unwrap();
+        let tagged: TaggedBuffer<U> = bincode::deserialize(&serialized[..]).unwrap();
+        unsafe { decompress_tagged(&tagged) }
+    }
+
+    #[test]
+    fn ok() {
+        let data: Vec<u32> = (0..1000).collect();
+        let tagged = TaggedBuffer::from(Context::new().compress(&data[..]));
+        assert_eq!(1000, tagged.elem_count());
+        assert_eq!("u32", tagged.type_name());
+        assert_eq!(Ok(data.clone()), round_trip::<u32, u32>(&data[..]));
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let data: Vec<u32> = (0..1000).collect();
+        assert_eq!(
+            Err(BloscError::TypeMismatch),
+            round_trip::<u32, i32>(&data[..])
+        );
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let data: Vec<u32> = (0..1000).collect();
+        let tagged = TaggedBuffer::from(Context::new().compress(&data[..]));
+        let mut serialized = bincode::serialize(&tagged).unwrap();
+        // bincode puts the element count right after the data
+        let count_offset = serialized.len() - 8 - 3 - 8;
+        serialized[count_offset] ^= 1;
+        let tagged: TaggedBuffer<u32> = bincode::deserialize(&serialized[..]).unwrap();
+        assert_eq!(1001, tagged.elem_count());
+        let r = unsafe { decompress_tagged(&tagged) };
+        assert_eq!(Err(BloscError::LengthMismatch), r);
     }
 }